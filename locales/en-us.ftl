@@ -0,0 +1,107 @@
+locale-name = English
+
+## Layout
+
+site-tagline = An ATProtocol identity validation and verification tool.
+footer-made-by = made by
+footer-source-code = Source code
+
+## Home page
+
+form-subject-label = Handle or DID
+form-submit = Verify
+
+## DID page
+
+did-caution = Examine all claims carefully and exercise caution.
+did-no-identities = The identifier has no identities associated with it.
+did-recheck = Re-check now
+did-help-heading = Help
+did-help-intro = How to read identity verification status:
+did-help-verified = the identity has been verified.
+did-help-unverified = the identity has <strong>not</strong> been verified.
+did-help-pending = the identity is being processed.
+did-help-cached = Results are cached for a while. If you just fixed an identity, use "Re-check now" to verify it again.
+did-help-bidirectional = Verification is <strong><em>bi-directional</em></strong>. Meaning if your DID document includes a link to an identity, the identity needs to link back or reference the DID.
+identity-verified = The identity { $identity } was verified.
+identity-unverified = The identity { $identity } was not verified.
+progress-busy = The server is busy and can't verify identities right now, try again shortly.
+progress-queued = The server is busy, position { $position } in queue.
+progress-checked = Checked { $completed } of { $total ->
+    [one] { $total } identity
+   *[other] { $total } identities
+}.
+summary-verified = { $verified } of { $total ->
+    [one] { $total } identity
+   *[other] { $total } identities
+} verified.
+
+## Guide
+
+guide-heading = Supported Identities
+guide-caution = <strong>Proceed With Caution!</strong> Updating your DID document comes with risk and you should be very careful when doing so.
+
+## Errors
+
+error-invalid-request = Invalid Request
+error-internal = Internal Server Error
+error-too-many-requests = You are making requests too quickly, try again in { $seconds ->
+    [one] { $seconds } second
+   *[other] { $seconds } seconds
+}.
+error-invalid-did = { $did } is not a valid DID.
+error-recheck-too-soon = { $did } was checked less than { $seconds } seconds ago, try again shortly.
+
+## Identity kinds
+
+label-handle = Handle
+label-domain = DNS
+label-did_method_plc = DID-PLC
+label-did_method_web = DID-WEB
+label-github = GitHub
+label-website = WWW
+label-unsupported = Unknown
+
+guide-handle = Add "at://" + handle to alsoKnownAs and create a TXT record at "_atproto." + handle with the value "did=" + DID.
+guide-domain = Add "dns:" + domain to alsoKnownAs and create a TXT record on the domain with the value "did=" + DID.
+guide-did_method_plc = Add the did:plc DID to alsoKnownAs and add this DID to the alsoKnownAs of the did:plc document.
+guide-did_method_web = Add the did:web DID to alsoKnownAs and add this DID to the alsoKnownAs of the did:web document.
+guide-github = Add your GitHub profile URL to alsoKnownAs and add a Bluesky social account to your GitHub profile that links to a handle for this DID.
+guide-website = Add the website URL to alsoKnownAs and include a <link rel="did" href="DID"> element in the page.
+
+## Verification reasons
+
+reason-message = { $text }
+reason-unsupported-identity = Unsupported identity
+reason-txt-mismatch = The TXT record of { $domain } references { $found }
+reason-txt-lookup-failed = Looking up the TXT record of { $domain } failed: { $error }
+reason-resolve-failed = Resolving { $value } failed: { $error }
+reason-also-known-as-missing = The alsoKnownAs of { $value } does not include { $did }
+reason-handle-mismatch = { $handle } resolves to { $found }
+reason-github-fetch-failed = Fetching the GitHub social accounts of { $user } failed: { $error }
+reason-github-read-failed = Reading the GitHub social accounts of { $user } failed: { $error }
+reason-github-no-bluesky = The GitHub profile of { $user } has no Bluesky social account
+reason-website-unsupported = { $url } is not a supported website URL
+reason-website-fetch-failed = Fetching { $url } failed: { $error }
+reason-website-read-failed = Reading { $url } failed: { $error }
+reason-host-rate-limited = Too many verifications contacted { $host } recently, try again shortly
+reason-website-no-link = { $url } has no link to { $did }
+
+## Remedies
+
+remedy-heading = How to fix this
+remedy-record-type = Type
+remedy-record-name = Name
+remedy-record-value = Value
+remedy-found = Found
+remedy-expected = Expected
+remedy-missing = Nothing
+remedy-handle-txt = Publish this TXT record at { $name }, replacing any other "did=" record, or serve the DID as plain text at https://{ $handle }/.well-known/atproto-did.
+remedy-domain-txt = Publish this TXT record on { $domain }, replacing any other "did=" record.
+remedy-also-known-as = Add { $did } to the alsoKnownAs of { $value }.
+remedy-resolve = Make sure { $value } can be resolved, then re-check.
+remedy-github-social = Add this URL as the Bluesky social account of { $user } in the GitHub profile settings at https://github.com/settings/profile.
+remedy-github-no-handle = Add a handle to the DID document, then link to it from a Bluesky social account in the GitHub profile.
+remedy-website-link = Add this element to the head of { $url }.
+remedy-website-fetch = Make sure { $url } responds with HTTP 200 within 3 seconds, and add this element to its head.
+remedy-website-url = Use an http or https URL without credentials or a query string.
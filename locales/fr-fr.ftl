@@ -0,0 +1,101 @@
+locale-name = Français
+
+## Layout
+
+site-tagline = Un outil de validation et de vérification des identités ATProtocol.
+footer-made-by = créé par
+footer-source-code = Code source
+
+## Home page
+
+form-subject-label = Pseudo ou DID
+form-submit = Vérifier
+
+## DID page
+
+did-caution = Examinez attentivement chaque affirmation et faites preuve de prudence.
+did-no-identities = Cet identifiant n'est associé à aucune identité.
+did-recheck = Revérifier maintenant
+did-help-heading = Aide
+did-help-intro = Comment lire l'état de vérification des identités :
+did-help-verified = l'identité a été vérifiée.
+did-help-unverified = l'identité n'a <strong>pas</strong> été vérifiée.
+did-help-pending = l'identité est en cours de traitement.
+did-help-cached = Les résultats sont conservés en cache pendant un certain temps. Si vous venez de corriger une identité, utilisez « Revérifier maintenant » pour la vérifier à nouveau.
+did-help-bidirectional = La vérification est <strong><em>bidirectionnelle</em></strong> : si votre document DID contient un lien vers une identité, l'identité doit à son tour renvoyer vers le DID.
+identity-verified = L'identité { $identity } a été vérifiée.
+identity-unverified = L'identité { $identity } n'a pas été vérifiée.
+progress-busy = Le serveur est occupé et ne peut pas vérifier les identités pour le moment, réessayez dans quelques instants.
+progress-queued = Le serveur est occupé, position { $position } dans la file d'attente.
+progress-checked = Identités contrôlées : { $completed } sur { $total }.
+summary-verified = Identités vérifiées : { $verified } sur { $total }.
+
+## Guide
+
+guide-heading = Identités prises en charge
+guide-caution = <strong>Soyez prudent !</strong> Modifier votre document DID comporte des risques et doit être fait avec beaucoup de précautions.
+
+## Errors
+
+error-invalid-request = Requête invalide
+error-internal = Erreur interne du serveur
+error-too-many-requests = Vous envoyez trop de requêtes, réessayez dans { $seconds ->
+    [one] { $seconds } seconde
+   *[other] { $seconds } secondes
+}.
+error-invalid-did = { $did } n'est pas un DID valide.
+error-recheck-too-soon = { $did } a été vérifié il y a moins de { $seconds } secondes, réessayez dans quelques instants.
+
+## Identity kinds
+
+label-handle = Pseudo
+label-domain = DNS
+label-did_method_plc = DID-PLC
+label-did_method_web = DID-WEB
+label-github = GitHub
+label-website = Web
+label-unsupported = Inconnue
+
+guide-handle = Ajoutez « at:// » + pseudo à alsoKnownAs et créez un enregistrement TXT sur « _atproto. » + pseudo avec la valeur « did= » + DID.
+guide-domain = Ajoutez « dns: » + domaine à alsoKnownAs et créez un enregistrement TXT sur le domaine avec la valeur « did= » + DID.
+guide-did_method_plc = Ajoutez le DID did:plc à alsoKnownAs et ajoutez ce DID à l'alsoKnownAs du document did:plc.
+guide-did_method_web = Ajoutez le DID did:web à alsoKnownAs et ajoutez ce DID à l'alsoKnownAs du document did:web.
+guide-github = Ajoutez l'URL de votre profil GitHub à alsoKnownAs et ajoutez à votre profil GitHub un compte social Bluesky qui pointe vers un pseudo de ce DID.
+guide-website = Ajoutez l'URL du site à alsoKnownAs et incluez un élément <link rel="did" href="DID"> dans la page.
+
+## Verification reasons
+
+reason-message = { $text }
+reason-unsupported-identity = Identité non prise en charge
+reason-txt-mismatch = L'enregistrement TXT de { $domain } référence { $found }
+reason-txt-lookup-failed = La recherche de l'enregistrement TXT de { $domain } a échoué : { $error }
+reason-resolve-failed = La résolution de { $value } a échoué : { $error }
+reason-also-known-as-missing = L'alsoKnownAs de { $value } ne contient pas { $did }
+reason-handle-mismatch = { $handle } se résout en { $found }
+reason-github-fetch-failed = La récupération des comptes sociaux GitHub de { $user } a échoué : { $error }
+reason-github-read-failed = La lecture des comptes sociaux GitHub de { $user } a échoué : { $error }
+reason-github-no-bluesky = Le profil GitHub de { $user } n'a pas de compte social Bluesky
+reason-website-unsupported = { $url } n'est pas une URL de site web prise en charge
+reason-website-fetch-failed = La récupération de { $url } a échoué : { $error }
+reason-website-read-failed = La lecture de { $url } a échoué : { $error }
+reason-host-rate-limited = Trop de vérifications ont contacté { $host } récemment, réessayez dans quelques instants
+reason-website-no-link = { $url } ne contient pas de lien vers { $did }
+
+## Remedies
+
+remedy-heading = Comment corriger
+remedy-record-type = Type
+remedy-record-name = Nom
+remedy-record-value = Valeur
+remedy-found = Trouvé
+remedy-expected = Attendu
+remedy-missing = Rien
+remedy-handle-txt = Publiez cet enregistrement TXT sur { $name } en remplaçant tout autre enregistrement « did= », ou servez le DID en texte brut à l'adresse https://{ $handle }/.well-known/atproto-did.
+remedy-domain-txt = Publiez cet enregistrement TXT sur { $domain } en remplaçant tout autre enregistrement « did= ».
+remedy-also-known-as = Ajoutez { $did } à l'alsoKnownAs de { $value }.
+remedy-resolve = Assurez-vous que { $value } peut être résolu, puis revérifiez.
+remedy-github-social = Ajoutez cette URL comme compte social Bluesky de { $user } dans les paramètres du profil GitHub, à l'adresse https://github.com/settings/profile.
+remedy-github-no-handle = Ajoutez un pseudo au document DID, puis liez-le depuis un compte social Bluesky du profil GitHub.
+remedy-website-link = Ajoutez cet élément à l'en-tête (head) de { $url }.
+remedy-website-fetch = Assurez-vous que { $url } répond avec le statut HTTP 200 en moins de 3 secondes, et ajoutez cet élément à son en-tête (head).
+remedy-website-url = Utilisez une URL http ou https sans identifiants ni paramètres de requête.
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::env;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use weathervane::{
+    config::{build_http_client, default_user_agent, CertificateBundles},
+    identity::Identity,
+    verifier::{VerificationEvent, Verifier, VerifierOptions},
+};
+
+const USAGE: &str = "Usage: weathervane-cli [--json] [--version] <handle or DID>
+       weathervane-cli [--json] --input <file>
+
+Verifies every alsoKnownAs identity of a handle or DID. Exits with a non-zero
+status if a subject cannot be resolved or any identity is not verified.
+
+Options:
+  --json           Print a JSON report, or one JSON report per line with --input
+  --input <file>   Verify each handle or DID listed in the file, one per line.
+                   Use \"-\" to read from standard input.
+
+Environment:
+  PLC_HOSTNAME          The PLC directory hostname (default: plc.directory)
+  USER_AGENT            The user agent used for outbound requests
+  CERTIFICATE_BUNDLES   Semicolon separated paths of additional CA certificates";
+
+const BATCH_CONCURRENCY: usize = 8;
+
+#[derive(Serialize)]
+struct Report {
+    subject: String,
+    did: Option<String>,
+    verified: bool,
+    error: Option<String>,
+    identities: Vec<IdentityReport>,
+}
+
+#[derive(Serialize)]
+struct IdentityReport {
+    #[serde(flatten)]
+    identity: Identity,
+    verified: bool,
+    reason: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    let mut json = false;
+    let mut input = None;
+    let mut subjects = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--version" => {
+                println!("{}", weathervane::config::version()?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(ExitCode::SUCCESS);
+            }
+            "--json" => json = true,
+            "--input" => match args.next() {
+                Some(path) => input = Some(path),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return Ok(ExitCode::from(2));
+                }
+            },
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                return Ok(ExitCode::from(2));
+            }
+            _ => subjects.push(arg),
+        }
+    }
+    if let Some(path) = &input {
+        if !subjects.is_empty() {
+            eprintln!("{}", USAGE);
+            return Ok(ExitCode::from(2));
+        }
+        subjects = read_subjects(path)?;
+    } else if subjects.len() != 1 {
+        eprintln!("{}", USAGE);
+        return Ok(ExitCode::from(2));
+    }
+
+    let certificate_bundles: CertificateBundles = env::var("CERTIFICATE_BUNDLES")
+        .unwrap_or_default()
+        .try_into()?;
+    let user_agent = env::var("USER_AGENT").or_else(|_| default_user_agent())?;
+    let http_client = build_http_client(&certificate_bundles, &user_agent)?;
+
+    let verifier = Verifier::new(
+        &http_client,
+        VerifierOptions {
+            plc_hostname: env::var("PLC_HOSTNAME").unwrap_or("plc.directory".to_string()),
+            ..Default::default()
+        },
+    );
+
+    if input.is_none() {
+        let report = verify_subject(&verifier, &subjects[0]).await;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_table(&report);
+        }
+
+        return Ok(if report.verified {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let mut reports = stream::iter(subjects)
+        .map(|subject| {
+            let verifier = verifier.clone();
+            async move { verify_subject(&verifier, &subject).await }
+        })
+        .buffered(BATCH_CONCURRENCY);
+
+    let mut verified = true;
+    let mut first = true;
+    while let Some(report) = reports.next().await {
+        verified &= report.verified;
+        if json {
+            println!("{}", serde_json::to_string(&report)?);
+        } else {
+            if !first {
+                println!();
+            }
+            print_table(&report);
+        }
+        first = false;
+    }
+
+    Ok(if verified {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn read_subjects(path: &str) -> Result<Vec<String>> {
+    let contents = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect())
+}
+
+async fn verify_subject(verifier: &Verifier, subject: &str) -> Report {
+    let mut report = Report {
+        subject: subject.to_string(),
+        did: None,
+        verified: false,
+        error: None,
+        identities: Vec::new(),
+    };
+
+    let did = match verifier.resolve_subject(subject).await {
+        Ok(did) => did,
+        Err(err) => {
+            report.error = Some(err.to_string());
+            return report;
+        }
+    };
+    report.did = Some(did.clone());
+
+    let mut events = std::pin::pin!(verifier.verify_all(&did));
+    while let Some(event) = events.next().await {
+        match event {
+            VerificationEvent::Document { .. } => {}
+            VerificationEvent::Failed { reason } => report.error = Some(reason),
+            VerificationEvent::Verified { identity } => report.identities.push(IdentityReport {
+                identity,
+                verified: true,
+                reason: None,
+            }),
+            VerificationEvent::Unverified { identity, reason } => {
+                report.identities.push(IdentityReport {
+                    identity,
+                    verified: false,
+                    reason: Some(reason),
+                })
+            }
+        }
+    }
+
+    report.verified =
+        report.error.is_none() && report.identities.iter().all(|identity| identity.verified);
+    report
+}
+
+fn print_table(report: &Report) {
+    let color = std::io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let paint = |code: &str, value: &str| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", code, value)
+        } else {
+            value.to_string()
+        }
+    };
+
+    match (&report.did, &report.error) {
+        (Some(did), _) if did != &report.subject => println!("{} ({})", report.subject, did),
+        _ => println!("{}", report.subject),
+    }
+    if let Some(error) = &report.error {
+        println!("{}", paint("31", &format!("error: {}", error)));
+        return;
+    }
+    if report.identities.is_empty() {
+        println!("The identifier has no identities associated with it.");
+        return;
+    }
+
+    let type_width = report
+        .identities
+        .iter()
+        .map(|identity| identity.identity.label.len())
+        .max()
+        .unwrap_or_default()
+        .max("TYPE".len());
+    let value_width = report
+        .identities
+        .iter()
+        .map(|identity| identity.identity.value.len())
+        .max()
+        .unwrap_or_default()
+        .max("IDENTITY".len());
+
+    println!(
+        "{:<10}  {:<type_width$}  {:<value_width$}  REASON",
+        "STATUS", "TYPE", "IDENTITY"
+    );
+    for identity in &report.identities {
+        let status = if identity.verified {
+            paint("32", &format!("{:<10}", "verified"))
+        } else {
+            paint("31", &format!("{:<10}", "unverified"))
+        };
+        println!(
+            "{}  {:<type_width$}  {:<value_width$}  {}",
+            status,
+            identity.identity.label,
+            identity.identity.value,
+            identity.reason.as_deref().unwrap_or_default()
+        );
+    }
+}
@@ -1,15 +1,18 @@
 use anyhow::Result;
-use std::env;
+use std::{env, net::SocketAddr};
 use tokio::net::TcpListener;
 use tokio::signal;
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
-use tracing_subscriber::prelude::*;
 use weathervane::{
-    cache::{new_did_document_cache, new_resolve_handle_cache},
+    health::ReadinessCheck,
     http::{
         context::{AppEngine, WebContext},
         server::build_router,
     },
+    jetstream::{JetstreamSource, JetstreamTask},
+    verifier::{Verifier, VerifierOptions},
+    watch::{WatchList, WatchTask},
+    webhook::WebhookNotifier,
     worker::{QueueWork, VerifyTask},
 };
 
@@ -21,12 +24,7 @@ use weathervane::http::templates::reload_env;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "weathervane=debug,info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer().pretty())
-        .init();
+    let _telemetry = weathervane::telemetry::init()?;
 
     let version = weathervane::config::version()?;
 
@@ -39,16 +37,10 @@ async fn main() -> Result<()> {
 
     let config = weathervane::config::Config::new()?;
 
-    let mut client_builder = reqwest::Client::builder();
-    for ca_certificate in config.certificate_bundles.as_ref() {
-        tracing::info!("Loading CA certificate: {:?}", ca_certificate);
-        let cert = std::fs::read(ca_certificate)?;
-        let cert = reqwest::Certificate::from_pem(&cert)?;
-        client_builder = client_builder.add_root_certificate(cert);
-    }
+    let metrics_handle = weathervane::monitoring::install_recorder()?;
 
-    client_builder = client_builder.user_agent(config.user_agent.clone());
-    let http_client = client_builder.build()?;
+    let http_client =
+        weathervane::config::build_http_client(&config.certificate_bundles, &config.user_agent)?;
 
     #[cfg(feature = "embed")]
     let jinja = embed_env::build_env(config.external_base.clone(), config.version.clone());
@@ -56,26 +48,43 @@ async fn main() -> Result<()> {
     #[cfg(feature = "reload")]
     let jinja = reload_env::build_env(&config.external_base, &config.version);
 
-    let (verify_work_tx, mut verify_work_rx) = tokio::sync::mpsc::channel::<QueueWork>(100);
+    let (verify_work_tx, mut verify_work_rx) =
+        tokio::sync::mpsc::channel::<QueueWork>(config.verify_queue_size);
+
+    let verifier = Verifier::new(
+        &http_client,
+        VerifierOptions {
+            plc_hostname: config.plc_hostname.clone(),
+            cache: config.cache.clone(),
+            host_rate_limit: config.rate_limits.outbound_host,
+        },
+    );
+
+    let tracker = TaskTracker::new();
+    let token = CancellationToken::new();
+
+    let verify_task = VerifyTask::new(verifier.clone(), config.verify_concurrency, token.clone());
 
-    let resolve_handle_cache = new_resolve_handle_cache();
-    let did_document_cache = new_did_document_cache();
+    let readiness_check = ReadinessCheck::new(
+        &http_client,
+        &config.plc_hostname,
+        verify_work_tx.clone(),
+        verify_task.status(),
+    );
+    let queue_progress = verify_task.progress();
 
     let web_context = WebContext::new(
-        config.external_base.as_str(),
+        &config,
         AppEngine::from(jinja),
-        &http_client,
-        verify_work_tx,
-        resolve_handle_cache,
-        did_document_cache,
-        config.plc_hostname.clone(),
+        verify_work_tx.clone(),
+        verifier.clone(),
+        metrics_handle.clone(),
+        readiness_check,
+        queue_progress,
     );
 
     let app = build_router(web_context.clone());
 
-    let tracker = TaskTracker::new();
-    let token = CancellationToken::new();
-
     {
         let tracker = tracker.clone();
         let inner_token = token.clone();
@@ -105,11 +114,55 @@ async fn main() -> Result<()> {
         });
     }
 
+    let watch_list = WatchList::new(
+        &http_client,
+        config.watched_dids.as_ref().clone(),
+        config.watched_dids_source.clone(),
+    );
+    if !watch_list.is_empty() {
+        let notifier = WebhookNotifier::new(
+            &http_client,
+            config.webhook_urls.as_ref().clone(),
+            config.webhook_secret.clone(),
+        );
+        let task = WatchTask::new(
+            verifier.clone(),
+            verify_work_tx.clone(),
+            notifier,
+            watch_list,
+            *config.watch_interval.as_ref(),
+            token.clone(),
+        );
+        let inner_token = token.clone();
+        tracker.spawn(async move {
+            let _ = task.run_background().await;
+            inner_token.cancel();
+        });
+    }
+
+    tracker.spawn(weathervane::monitoring::run_upkeep(
+        metrics_handle,
+        token.clone(),
+    ));
+
+    let jetstream_source = match (config.jetstream_url.as_ref(), &config.jetstream_replay) {
+        (_, Some(path)) => Some(JetstreamSource::Replay(path.clone())),
+        (Some(url), None) => Some(JetstreamSource::WebSocket(url.clone())),
+        (None, None) => None,
+    };
+    if let Some(source) = jetstream_source {
+        let task = JetstreamTask::new(verifier.clone(), source, token.clone());
+        tracker.spawn(async move {
+            if let Err(err) = task.run_background().await {
+                tracing::error!(error = ?err, "jetstream task failed");
+            }
+        });
+    }
+
     {
-        let task = VerifyTask::new(&http_client, config.plc_hostname.clone(), token.clone());
         let inner_token = token.clone();
         tracker.spawn(async move {
-            let _ = task.run_background(&mut verify_work_rx).await;
+            let _ = verify_task.run_background(&mut verify_work_rx).await;
             inner_token.cancel();
         });
     }
@@ -124,14 +177,17 @@ async fn main() -> Result<()> {
                 .unwrap();
 
             let shutdown_token = inner_token.clone();
-            let result = axum::serve(listener, app)
-                .with_graceful_shutdown(async move {
-                    tokio::select! {
-                        () = shutdown_token.cancelled() => { }
-                    }
-                    tracing::info!("axum graceful shutdown complete");
-                })
-                .await;
+            let result = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move {
+                tokio::select! {
+                    () = shutdown_token.cancelled() => { }
+                }
+                tracing::info!("axum graceful shutdown complete");
+            })
+            .await;
             if let Err(err) = result {
                 tracing::error!("axum task failed: {}", err);
             }
@@ -1,27 +1,123 @@
 use anyhow::{anyhow, Result};
 use moka::{future::Cache, Expiry};
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    did_plc::plc_query,
-    did_web::web_query,
+    did::{DidDocument, DidMethodResolvers},
+    errors::{is_transient_error, UnreachableError},
+    i18n::Reason,
+    identity::Identity,
+    monitoring::record_cache_lookup,
+    rate_limit::RateLimiter,
     resolve::{parse_input, resolve_subject, InputType},
 };
 
-struct VerifyWorkExpiry;
-struct ResolveHandleExpiry;
-struct DidDocumentExpiry;
+/// How long results are cached, by outcome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheTtls {
+    pub found: Duration,
+    /// Definitive failures, such as an identity that does not reference the DID.
+    pub not_found: Duration,
+    /// Transient failures, such as timeouts and server errors.
+    pub unreachable: Duration,
+}
+
+/// The capacity and TTLs of each cache.
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    pub resolve_handle_capacity: u64,
+    pub resolve_handle_ttls: CacheTtls,
+    pub did_document_capacity: u64,
+    pub did_document_ttls: CacheTtls,
+    pub verify_capacity: u64,
+    pub verify_ttls: CacheTtls,
+    /// Verification TTLs of identity kinds that differ from `verify_ttls`.
+    pub verify_kind_ttls: HashMap<String, CacheTtls>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            resolve_handle_capacity: 1024 * 20,
+            resolve_handle_ttls: CacheTtls {
+                found: Duration::from_secs(60 * 5),
+                not_found: Duration::from_secs(60 * 120),
+                unreachable: Duration::from_secs(60),
+            },
+            did_document_capacity: 1024 * 20,
+            did_document_ttls: CacheTtls {
+                found: Duration::from_secs(60 * 5),
+                not_found: Duration::from_secs(60 * 120),
+                unreachable: Duration::from_secs(60),
+            },
+            verify_capacity: 1024 * 20,
+            verify_ttls: CacheTtls {
+                found: Duration::from_secs(60 * 10),
+                not_found: Duration::from_secs(60 * 60),
+                unreachable: Duration::from_secs(60),
+            },
+            verify_kind_ttls: HashMap::new(),
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn verify_ttls_for(&self, kind: &str) -> &CacheTtls {
+        self.verify_kind_ttls.get(kind).unwrap_or(&self.verify_ttls)
+    }
+
+    /// The shortest time a DID document or verification result is cached for.
+    pub(crate) fn shortest_found_ttl(&self) -> Duration {
+        self.verify_kind_ttls
+            .values()
+            .map(|ttls| ttls.found)
+            .chain([self.did_document_ttls.found, self.verify_ttls.found])
+            .min()
+            .unwrap_or(self.verify_ttls.found)
+    }
+}
+
+impl CacheTtls {
+    fn longest(&self) -> Duration {
+        self.found.max(self.not_found).max(self.unreachable)
+    }
+}
+
+impl CacheConfig {
+    /// The longest time any result is cached for.
+    pub(crate) fn longest_ttl(&self) -> Duration {
+        self.verify_kind_ttls
+            .values()
+            .chain([
+                &self.resolve_handle_ttls,
+                &self.did_document_ttls,
+                &self.verify_ttls,
+            ])
+            .map(CacheTtls::longest)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+struct VerifyWorkExpiry(CacheConfig);
+struct ResolveHandleExpiry(CacheTtls);
+struct DidDocumentExpiry(CacheTtls);
 
-impl Expiry<String, VerifyResult> for VerifyWorkExpiry {
+impl Expiry<String, VerifyCacheEntry> for VerifyWorkExpiry {
     fn expire_after_create(
         &self,
         _key: &String,
-        value: &VerifyResult,
+        value: &VerifyCacheEntry,
         _current_time: Instant,
     ) -> Option<Duration> {
-        match value {
-            VerifyResult::Found => Some(Duration::from_secs(60 * 10)),
-            VerifyResult::NotFound => Some(Duration::from_secs(60 * 60)),
+        let ttls = self.0.verify_ttls_for(&value.kind);
+        match value.result {
+            VerifyResult::Found => Some(ttls.found),
+            VerifyResult::NotFound(_) => Some(ttls.not_found),
+            VerifyResult::Unreachable(_) => Some(ttls.unreachable),
         }
     }
 }
@@ -34,8 +130,9 @@ impl Expiry<String, ResolveHandleResult> for ResolveHandleExpiry {
         _current_time: Instant,
     ) -> Option<Duration> {
         match value {
-            ResolveHandleResult::Found(_) => Some(Duration::from_secs(60 * 5)),
-            ResolveHandleResult::NotFound(_) => Some(Duration::from_secs(60 * 120)),
+            ResolveHandleResult::Found(_) => Some(self.0.found),
+            ResolveHandleResult::NotFound(_) => Some(self.0.not_found),
+            ResolveHandleResult::Unreachable(_) => Some(self.0.unreachable),
         }
     }
 }
@@ -48,83 +145,154 @@ impl Expiry<String, DidDocumentResult> for DidDocumentExpiry {
         _current_time: Instant,
     ) -> Option<Duration> {
         match value {
-            DidDocumentResult::Found(_, _) => Some(Duration::from_secs(60 * 5)),
-            DidDocumentResult::NotFound(_) => Some(Duration::from_secs(60 * 120)),
+            DidDocumentResult::Found(_) => Some(self.0.found),
+            DidDocumentResult::NotFound(_) => Some(self.0.not_found),
+            DidDocumentResult::Unreachable(_) => Some(self.0.unreachable),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub(crate) enum VerifyResult {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyResult {
     Found,
-    NotFound,
+    /// The identity does not reference the DID.
+    NotFound(Reason),
+    /// The identity could not be checked, such as when a request timed out.
+    Unreachable(Reason),
+}
+
+impl VerifyResult {
+    /// The failure for an error, classified by whether it is transient. The error is added to
+    /// the reason as the `error` argument.
+    pub(crate) fn from_error(reason: Reason, err: &anyhow::Error) -> Self {
+        let reason = reason.arg("error", err.to_string());
+        if is_transient_error(err) {
+            VerifyResult::Unreachable(reason)
+        } else {
+            VerifyResult::NotFound(reason)
+        }
+    }
+
+    /// The reason the identity was not verified.
+    pub fn reason(&self) -> Option<&Reason> {
+        match self {
+            VerifyResult::Found => None,
+            VerifyResult::NotFound(reason) | VerifyResult::Unreachable(reason) => Some(reason),
+        }
+    }
+}
+
+/// A cached verification result, with the identity kind that decides how long it is kept.
+#[derive(Clone)]
+pub struct VerifyCacheEntry {
+    pub(crate) kind: String,
+    pub(crate) result: VerifyResult,
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum ResolveHandleResult {
     Found(String),
     NotFound(String),
+    Unreachable(String),
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum DidDocumentResult {
-    Found(String, Vec<String>),
+    Found(DidDocument),
     NotFound(String),
+    Unreachable(String),
 }
 
-pub(crate) fn new_worker_cache() -> Cache<String, VerifyResult> {
-    let expiry = VerifyWorkExpiry;
+pub fn new_worker_cache(config: &CacheConfig) -> Cache<String, VerifyCacheEntry> {
+    let expiry = VerifyWorkExpiry(config.clone());
     Cache::builder()
-        .max_capacity(1024 * 20)
+        .max_capacity(config.verify_capacity)
         .expire_after(expiry)
+        .support_invalidation_closures()
         .build()
 }
 
-pub fn new_resolve_handle_cache() -> Cache<String, ResolveHandleResult> {
-    let expiry = ResolveHandleExpiry;
+pub fn new_resolve_handle_cache(config: &CacheConfig) -> Cache<String, ResolveHandleResult> {
+    let expiry = ResolveHandleExpiry(config.resolve_handle_ttls);
     Cache::builder()
-        .max_capacity(1024 * 20)
+        .max_capacity(config.resolve_handle_capacity)
         .expire_after(expiry)
+        .support_invalidation_closures()
         .build()
 }
 
-pub fn new_did_document_cache() -> Cache<String, DidDocumentResult> {
-    let expiry = DidDocumentExpiry;
+pub fn new_did_document_cache(config: &CacheConfig) -> Cache<String, DidDocumentResult> {
+    let expiry = DidDocumentExpiry(config.did_document_ttls);
     Cache::builder()
-        .max_capacity(1024 * 20)
+        .max_capacity(config.did_document_capacity)
         .expire_after(expiry)
+        .support_invalidation_closures()
         .build()
 }
 
+pub(crate) fn subject_cache_key(subject: &str) -> String {
+    cityhasher::hash::<u64>(subject).to_string()
+}
+
+pub(crate) fn did_document_cache_key(did: &str) -> String {
+    cityhasher::hash::<u64>(did).to_string()
+}
+
+pub(crate) fn verify_cache_key(did: &str, identity: &Identity) -> String {
+    format!("{}-{}", did, identity.to_key())
+}
+
+/// Splits a verify cache key into the DID and the identity key. Identity keys are numbers, so
+/// the last `-` separates them.
+pub(crate) fn split_verify_cache_key(key: &str) -> Option<(&str, &str)> {
+    key.rsplit_once('-')
+}
+
+/// Resolves a subject, waiting for the host limiter before resolving a handle that is not cached.
+#[tracing::instrument(skip(cache, http_client, host_limiter))]
 pub(crate) async fn resolve_subject_cached(
     cache: Cache<String, ResolveHandleResult>,
     http_client: &reqwest::Client,
+    host_limiter: Option<&RateLimiter>,
     subject: &str,
 ) -> Result<String> {
-    let cache_key = cityhasher::hash::<u64>(subject).to_string();
-    if let Some(resolve_handle_result) = cache.get(&cache_key).await {
-        return match resolve_handle_result {
-            ResolveHandleResult::Found(did) => Ok(did),
-            ResolveHandleResult::NotFound(err) => Err(anyhow!(err)),
-        };
-    }
-    let resolved_did = resolve_subject(http_client, subject).await;
-
-    let cache_value = match resolved_did.as_ref() {
-        Ok(did) => ResolveHandleResult::Found(did.clone()),
-        Err(err) => ResolveHandleResult::NotFound(err.to_string()),
-    };
+    // Concurrent lookups of the same subject wait for a single resolution.
+    let mut miss = false;
+    let resolve_handle_result = cache
+        .get_with(subject_cache_key(subject), async {
+            miss = true;
+            if let (Some(host_limiter), Ok(InputType::Handle(handle))) =
+                (host_limiter, parse_input(subject))
+            {
+                host_limiter.acquire(&handle.to_lowercase()).await;
+            }
+            match resolve_subject(http_client, subject).await {
+                Ok(did) => ResolveHandleResult::Found(did),
+                Err(err) if is_transient_error(&err) => {
+                    ResolveHandleResult::Unreachable(err.to_string())
+                }
+                Err(err) => ResolveHandleResult::NotFound(err.to_string()),
+            }
+        })
+        .await;
+    record_cache_lookup("resolve_handle", !miss);
 
-    cache.insert(cache_key, cache_value).await;
-    resolved_did
+    match resolve_handle_result {
+        ResolveHandleResult::Found(did) => Ok(did),
+        ResolveHandleResult::NotFound(err) => Err(anyhow!(err)),
+        ResolveHandleResult::Unreachable(err) => Err(UnreachableError(err).into()),
+    }
 }
 
+/// Resolves a DID document, waiting for the host limiter before fetching a document that is not
+/// cached.
+#[tracing::instrument(skip(cache, did_method_resolvers, host_limiter))]
 pub(crate) async fn did_document_cached(
     cache: Cache<String, DidDocumentResult>,
-    http_client: &reqwest::Client,
-    plc_hostname: &str,
+    did_method_resolvers: &DidMethodResolvers,
+    host_limiter: Option<&RateLimiter>,
     did: &str,
-) -> Result<(String, Vec<String>)> {
+) -> Result<DidDocument> {
     let parsed_did = parse_input(did);
 
     if parsed_did.is_err() {
@@ -133,26 +301,106 @@ pub(crate) async fn did_document_cached(
         return Err(anyhow!("Invalid DID"));
     }
 
-    let cache_key = cityhasher::hash::<u64>(did).to_string();
-    if let Some(resolve_handle_result) = cache.get(&cache_key).await {
-        return match resolve_handle_result {
-            DidDocumentResult::Found(did, identities) => Ok((did, identities)),
-            DidDocumentResult::NotFound(err) => Err(anyhow!(err)),
-        };
+    // Concurrent lookups of the same DID wait for a single resolution.
+    let mut miss = false;
+    let did_document_result = cache
+        .get_with(did_document_cache_key(did), async {
+            miss = true;
+            if let (Some(host_limiter), Some(host)) =
+                (host_limiter, did_method_resolvers.target_host(did))
+            {
+                host_limiter.acquire(&host).await;
+            }
+            match did_method_resolvers.resolve(did).await {
+                Ok(document) => DidDocumentResult::Found(document),
+                Err(err) if is_transient_error(&err) => {
+                    DidDocumentResult::Unreachable(err.to_string())
+                }
+                Err(err) => DidDocumentResult::NotFound(err.to_string()),
+            }
+        })
+        .await;
+    record_cache_lookup("did_document", !miss);
+
+    match did_document_result {
+        DidDocumentResult::Found(document) => Ok(document),
+        DidDocumentResult::NotFound(err) => Err(anyhow!(err)),
+        DidDocumentResult::Unreachable(err) => Err(UnreachableError(err).into()),
     }
+}
 
-    let query_results = match parse_input(did) {
-        Ok(InputType::Plc(did)) => plc_query(http_client, plc_hostname, &did).await,
-        Ok(InputType::Web(did)) => web_query(http_client, &did).await,
-        _ => unreachable!(),
-    };
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use axum::Router;
+    use std::sync::Arc;
 
-    let cache_value = match query_results.as_ref() {
-        Ok((did, identities)) => DidDocumentResult::Found(did.clone(), identities.clone()),
-        Err(err) => DidDocumentResult::NotFound(err.to_string()),
+    use super::*;
+    use crate::{
+        did::{fetch_did_document, DidMethodResolver},
+        test_server::serve_failures,
     };
 
-    cache.insert(cache_key, cache_value).await;
+    /// Resolves `did:test:<path>` by fetching `<path>` from a local server.
+    struct TestResolver {
+        http_client: reqwest::Client,
+        base: String,
+    }
+
+    #[async_trait]
+    impl DidMethodResolver for TestResolver {
+        fn method(&self) -> &str {
+            "test"
+        }
+
+        async fn resolve(&self, did: &str) -> Result<DidDocument> {
+            let path = did.strip_prefix("did:test:").unwrap();
+            fetch_did_document(&self.http_client, &format!("{}/{}", self.base, path)).await
+        }
+    }
+
+    /// Looks up a DID twice, so that the second error is read from the cache, and returns the
+    /// cached result.
+    async fn cached_document(did: &str) -> DidDocumentResult {
+        let base = serve_failures(Router::new()).await;
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        let resolvers = DidMethodResolvers::new(vec![Arc::new(TestResolver { http_client, base })]);
+        let cache = new_did_document_cache(&CacheConfig::default());
 
-    query_results
+        let first = did_document_cached(cache.clone(), &resolvers, None, did).await;
+        let cached = did_document_cached(cache.clone(), &resolvers, None, did).await;
+        // Errors read from the cache are classified the same way as the original error.
+        assert_eq!(
+            is_transient_error(&first.unwrap_err()),
+            is_transient_error(&cached.unwrap_err())
+        );
+        cache.get(&did_document_cache_key(did)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_unreachable() {
+        assert!(matches!(
+            cached_document("did:test:busy").await,
+            DidDocumentResult::Unreachable(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_unreachable() {
+        assert!(matches!(
+            cached_document("did:test:slow").await,
+            DidDocumentResult::Unreachable(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn missing_documents_are_not_found() {
+        assert!(matches!(
+            cached_document("did:test:missing").await,
+            DidDocumentResult::NotFound(_)
+        ));
+    }
 }
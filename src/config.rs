@@ -1,4 +1,10 @@
 use anyhow::{anyhow, Result};
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    cache::{CacheConfig, CacheTtls},
+    rate_limit::{RateLimit, RateLimits},
+};
 
 #[derive(Clone)]
 pub struct HttpPort(u16);
@@ -6,6 +12,18 @@ pub struct HttpPort(u16);
 #[derive(Clone)]
 pub struct CertificateBundles(Vec<String>);
 
+#[derive(Clone)]
+pub struct WatchedDids(Vec<String>);
+
+#[derive(Clone)]
+pub struct WebhookUrls(Vec<String>);
+
+#[derive(Clone)]
+pub struct WatchInterval(Duration);
+
+#[derive(Clone)]
+pub struct JetstreamUrl(Option<String>);
+
 #[derive(Clone)]
 pub struct Config {
     pub version: String,
@@ -14,24 +32,65 @@ pub struct Config {
     pub certificate_bundles: CertificateBundles,
     pub user_agent: String,
     pub plc_hostname: String,
+    pub verify_concurrency: usize,
+    pub verify_queue_size: usize,
+    pub watched_dids: WatchedDids,
+    pub watched_dids_source: Option<String>,
+    pub watch_interval: WatchInterval,
+    pub webhook_urls: WebhookUrls,
+    pub webhook_secret: Option<String>,
+    pub jetstream_url: JetstreamUrl,
+    pub jetstream_replay: Option<String>,
+    pub admin_token: Option<String>,
+    pub cache: CacheConfig,
+    pub rate_limits: RateLimits,
 }
 
 impl Config {
     pub fn new() -> Result<Self> {
-        let http_port: HttpPort = default_env("HTTP_PORT", "4060").try_into()?;
-        let external_base = require_env("EXTERNAL_BASE")?;
+        let env = ConfigEnv::load()?;
+
+        let http_port: HttpPort = env.default("HTTP_PORT", "4060").try_into()?;
+        let external_base = env.require("EXTERNAL_BASE")?;
 
         let certificate_bundles: CertificateBundles =
-            optional_env("CERTIFICATE_BUNDLES").try_into()?;
+            env.optional("CERTIFICATE_BUNDLES").try_into()?;
 
-        let default_user_agent = format!(
-            "weathervane ({}; +https://github.com/astrenoxcoop/weathervane)",
-            version()?
-        );
+        let user_agent = env.default("USER_AGENT", &default_user_agent()?);
 
-        let user_agent = default_env("USER_AGENT", &default_user_agent);
+        let plc_hostname = env.default("PLC_HOSTNAME", "plc.directory");
+
+        let verify_concurrency = env
+            .default("VERIFY_CONCURRENCY", "10")
+            .parse::<usize>()
+            .map_err(|err| {
+                anyhow::Error::new(err)
+                    .context(anyhow!("parsing VERIFY_CONCURRENCY into usize failed"))
+            })?;
+
+        let verify_queue_size = env
+            .default("VERIFY_QUEUE_SIZE", "100")
+            .parse::<usize>()
+            .map_err(|err| {
+                anyhow::Error::new(err)
+                    .context(anyhow!("parsing VERIFY_QUEUE_SIZE into usize failed"))
+            })?;
+        if verify_queue_size == 0 {
+            return Err(anyhow!("VERIFY_QUEUE_SIZE must be greater than zero"));
+        }
 
-        let plc_hostname = default_env("PLC_HOSTNAME", "plc.directory");
+        let watched_dids: WatchedDids = env.optional("WATCHED_DIDS").try_into()?;
+        let watched_dids_source =
+            Some(env.optional("WATCHED_DIDS_SOURCE")).filter(|value| !value.is_empty());
+        let watch_interval: WatchInterval = env.default("WATCH_INTERVAL", "15m").try_into()?;
+        let webhook_urls: WebhookUrls = env.optional("WEBHOOK_URLS").try_into()?;
+        let webhook_secret = Some(env.optional("WEBHOOK_SECRET")).filter(|value| !value.is_empty());
+        let jetstream_url: JetstreamUrl = env.optional("JETSTREAM_URL").try_into()?;
+        let jetstream_replay =
+            Some(env.optional("JETSTREAM_REPLAY")).filter(|value| !value.is_empty());
+        let admin_token = Some(env.optional("ADMIN_TOKEN")).filter(|value| !value.is_empty());
+        let cache = cache_config(&env)?;
+        let rate_limits = rate_limits(&env)?;
 
         Ok(Self {
             version: version()?,
@@ -40,21 +99,215 @@ impl Config {
             certificate_bundles,
             user_agent,
             plc_hostname,
+            verify_concurrency,
+            verify_queue_size,
+            watched_dids,
+            watched_dids_source,
+            watch_interval,
+            webhook_urls,
+            webhook_secret,
+            jetstream_url,
+            jetstream_replay,
+            admin_token,
+            cache,
+            rate_limits,
         })
     }
 }
 
-fn require_env(name: &str) -> Result<String> {
-    std::env::var(name)
-        .map_err(|err| anyhow::Error::new(err).context(anyhow!("{} must be set", name)))
+/// Configuration values from the environment, falling back to the `KEY=VALUE` lines of the
+/// file named by `CONFIG_FILE`.
+struct ConfigEnv(HashMap<String, String>);
+
+impl ConfigEnv {
+    fn load() -> Result<Self> {
+        let mut values = HashMap::new();
+
+        if let Ok(config_file) = std::env::var("CONFIG_FILE") {
+            let contents = std::fs::read_to_string(&config_file).map_err(|err| {
+                anyhow::Error::new(err).context(anyhow!("reading CONFIG_FILE failed"))
+            })?;
+            for line in contents
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            {
+                let (name, value) = line
+                    .split_once('=')
+                    .ok_or(anyhow!("CONFIG_FILE contains an invalid line: {}", line))?;
+                values.insert(name.trim().to_string(), unquote(value.trim()).to_string());
+            }
+        }
+
+        values.extend(std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }));
+
+        Ok(Self(values))
+    }
+
+    fn require(&self, name: &str) -> Result<String> {
+        self.0
+            .get(name)
+            .cloned()
+            .ok_or(anyhow!("{} must be set", name))
+    }
+
+    fn optional(&self, name: &str) -> String {
+        self.0.get(name).cloned().unwrap_or_default()
+    }
+
+    fn default(&self, name: &str, default_value: &str) -> String {
+        self.0
+            .get(name)
+            .cloned()
+            .unwrap_or(default_value.to_string())
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| {
+            value
+                .strip_prefix(*quote)
+                .and_then(|value| value.strip_suffix(*quote))
+        })
+        .unwrap_or(value)
+}
+
+const CACHE_TTL_CLASSES: [&str; 3] = ["FOUND", "NOT_FOUND", "UNREACHABLE"];
+
+/// Reads cache capacities from `CACHE_<CACHE>_CAPACITY` and TTLs from
+/// `CACHE_<CACHE>_<CLASS>_TTL`, where the cache is `RESOLVE_HANDLE`, `DID_DOCUMENT` or `VERIFY`
+/// and the class is `FOUND`, `NOT_FOUND` or `UNREACHABLE`.
+///
+/// Verification TTLs can be set for a single identity kind with
+/// `CACHE_VERIFY_<CLASS>_TTL_<KIND>`, such as `CACHE_VERIFY_FOUND_TTL_GITHUB`.
+fn cache_config(env: &ConfigEnv) -> Result<CacheConfig> {
+    let defaults = CacheConfig::default();
+
+    let verify_ttls = cache_ttls(env, "CACHE_VERIFY", "", &defaults.verify_ttls)?;
+
+    let mut verify_kind_ttls = HashMap::new();
+    for name in env.0.keys() {
+        let kind = CACHE_TTL_CLASSES
+            .iter()
+            .find_map(|class| name.strip_prefix(&format!("CACHE_VERIFY_{}_TTL_", class)));
+        if let Some(kind) = kind.filter(|kind| !kind.is_empty()) {
+            let ttls = cache_ttls(env, "CACHE_VERIFY", &format!("_{}", kind), &verify_ttls)?;
+            verify_kind_ttls.insert(kind.to_lowercase(), ttls);
+        }
+    }
+
+    Ok(CacheConfig {
+        resolve_handle_capacity: cache_capacity(
+            env,
+            "CACHE_RESOLVE_HANDLE",
+            defaults.resolve_handle_capacity,
+        )?,
+        resolve_handle_ttls: cache_ttls(
+            env,
+            "CACHE_RESOLVE_HANDLE",
+            "",
+            &defaults.resolve_handle_ttls,
+        )?,
+        did_document_capacity: cache_capacity(
+            env,
+            "CACHE_DID_DOCUMENT",
+            defaults.did_document_capacity,
+        )?,
+        did_document_ttls: cache_ttls(env, "CACHE_DID_DOCUMENT", "", &defaults.did_document_ttls)?,
+        verify_capacity: cache_capacity(env, "CACHE_VERIFY", defaults.verify_capacity)?,
+        verify_ttls,
+        verify_kind_ttls,
+    })
+}
+
+/// Reads request limits from `RATE_LIMIT_VALIDATE`, `RATE_LIMIT_UPDATES`, `RATE_LIMIT_API`,
+/// `RATE_LIMIT_BATCH` and `RATE_LIMIT_OUTBOUND_HOST`, formatted as `COUNT/PERIOD` or `off`, and the client IP header
+/// from `TRUSTED_PROXY_HEADER`.
+fn rate_limits(env: &ConfigEnv) -> Result<RateLimits> {
+    let defaults = RateLimits::default();
+    Ok(RateLimits {
+        validate: rate_limit(env, "RATE_LIMIT_VALIDATE", defaults.validate)?,
+        updates: rate_limit(env, "RATE_LIMIT_UPDATES", defaults.updates)?,
+        api: rate_limit(env, "RATE_LIMIT_API", defaults.api)?,
+        batch: rate_limit(env, "RATE_LIMIT_BATCH", defaults.batch)?,
+        outbound_host: rate_limit(env, "RATE_LIMIT_OUTBOUND_HOST", defaults.outbound_host)?,
+        trusted_proxy_header: Some(env.optional("TRUSTED_PROXY_HEADER"))
+            .filter(|value| !value.is_empty()),
+    })
+}
+
+fn rate_limit(
+    env: &ConfigEnv,
+    name: &str,
+    default_value: Option<RateLimit>,
+) -> Result<Option<RateLimit>> {
+    match env.0.get(name).map(|value| value.as_str()) {
+        Some("off") => Ok(None),
+        Some(value) => RateLimit::try_from(value.to_string())
+            .map(Some)
+            .map_err(|err| err.context(anyhow!("parsing {} failed", name))),
+        None => Ok(default_value),
+    }
+}
+
+fn cache_capacity(env: &ConfigEnv, prefix: &str, default_value: u64) -> Result<u64> {
+    let name = format!("{}_CAPACITY", prefix);
+    match env.0.get(&name) {
+        Some(value) => value.parse::<u64>().map_err(|err| {
+            anyhow::Error::new(err).context(anyhow!("parsing {} into u64 failed", name))
+        }),
+        None => Ok(default_value),
+    }
+}
+
+fn cache_ttls(
+    env: &ConfigEnv,
+    prefix: &str,
+    suffix: &str,
+    defaults: &CacheTtls,
+) -> Result<CacheTtls> {
+    let ttl = |class: &str, default_value: Duration| -> Result<Duration> {
+        let name = format!("{}_{}_TTL{}", prefix, class, suffix);
+        match env.0.get(&name) {
+            Some(value) => duration_str::parse(value)
+                .map_err(|err| anyhow!("parsing {} failed: {}", name, err)),
+            None => Ok(default_value),
+        }
+    };
+
+    Ok(CacheTtls {
+        found: ttl(CACHE_TTL_CLASSES[0], defaults.found)?,
+        not_found: ttl(CACHE_TTL_CLASSES[1], defaults.not_found)?,
+        unreachable: ttl(CACHE_TTL_CLASSES[2], defaults.unreachable)?,
+    })
 }
 
-fn optional_env(name: &str) -> String {
-    std::env::var(name).unwrap_or("".to_string())
+pub fn default_user_agent() -> Result<String> {
+    Ok(format!(
+        "weathervane ({}; +https://github.com/astrenoxcoop/weathervane)",
+        version()?
+    ))
 }
 
-fn default_env(name: &str, default_value: &str) -> String {
-    std::env::var(name).unwrap_or(default_value.to_string())
+/// Builds the HTTP client used for outbound requests, trusting any additional CA certificates.
+pub fn build_http_client(
+    certificate_bundles: &CertificateBundles,
+    user_agent: &str,
+) -> Result<reqwest::Client> {
+    let mut client_builder = reqwest::Client::builder();
+    for ca_certificate in certificate_bundles.as_ref() {
+        tracing::info!("Loading CA certificate: {:?}", ca_certificate);
+        let cert = std::fs::read(ca_certificate)?;
+        let cert = reqwest::Certificate::from_pem(&cert)?;
+        client_builder = client_builder.add_root_certificate(cert);
+    }
+
+    client_builder = client_builder.user_agent(user_agent);
+    Ok(client_builder.build()?)
 }
 
 pub fn version() -> Result<String> {
@@ -83,21 +336,23 @@ impl AsRef<u16> for HttpPort {
     }
 }
 
+fn split_values(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .filter_map(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.to_string())
+            }
+        })
+        .collect::<Vec<String>>()
+}
+
 impl TryFrom<String> for CertificateBundles {
     type Error = anyhow::Error;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        Ok(Self(
-            value
-                .split(';')
-                .filter_map(|s| {
-                    if s.is_empty() {
-                        None
-                    } else {
-                        Some(s.to_string())
-                    }
-                })
-                .collect::<Vec<String>>(),
-        ))
+        Ok(Self(split_values(&value)))
     }
 }
 
@@ -106,3 +361,76 @@ impl AsRef<Vec<String>> for CertificateBundles {
         &self.0
     }
 }
+
+impl TryFrom<String> for WatchedDids {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let dids = split_values(&value);
+        if let Some(did) = dids.iter().find(|did| !did.starts_with("did:")) {
+            return Err(anyhow!("WATCHED_DIDS contains an invalid DID: {}", did));
+        }
+        Ok(Self(dids))
+    }
+}
+
+impl AsRef<Vec<String>> for WatchedDids {
+    fn as_ref(&self) -> &Vec<String> {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for WebhookUrls {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let urls = split_values(&value);
+        for url in &urls {
+            url::Url::parse(url).map_err(|err| {
+                anyhow::Error::new(err).context(anyhow!("parsing WEBHOOK_URLS failed"))
+            })?;
+        }
+        Ok(Self(urls))
+    }
+}
+
+impl AsRef<Vec<String>> for WebhookUrls {
+    fn as_ref(&self) -> &Vec<String> {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for WatchInterval {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        duration_str::parse(&value)
+            .map(Self)
+            .map_err(|err| anyhow!("parsing WATCH_INTERVAL failed: {}", err))
+    }
+}
+
+impl AsRef<Duration> for WatchInterval {
+    fn as_ref(&self) -> &Duration {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for JetstreamUrl {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(Self(None));
+        }
+        let url = url::Url::parse(&value).map_err(|err| {
+            anyhow::Error::new(err).context(anyhow!("parsing JETSTREAM_URL failed"))
+        })?;
+        if url.scheme() != "ws" && url.scheme() != "wss" {
+            return Err(anyhow!("JETSTREAM_URL must be a ws or wss URL"));
+        }
+        Ok(Self(Some(value)))
+    }
+}
+
+impl AsRef<Option<String>> for JetstreamUrl {
+    fn as_ref(&self) -> &Option<String> {
+        &self.0
+    }
+}
@@ -1,12 +1,151 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    did_key::KeyResolver, did_plc::PlcResolver, did_web::WebResolver, did_webvh::WebVhResolver,
+};
 
 #[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ResolveDid {
     pub(crate) id: String,
+    #[serde(default)]
     pub(crate) also_known_as: Vec<String>,
 }
 
+/// A resolved DID document along with the fields that Weather Vane uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DidDocument {
+    pub id: String,
+    pub also_known_as: Vec<String>,
+    pub document: serde_json::Value,
+}
+
+impl DidDocument {
+    pub(crate) fn from_value(document: serde_json::Value) -> Result<Self> {
+        let resolved_did: ResolveDid = serde_json::from_value(document.clone())?;
+        Ok(Self {
+            id: resolved_did.id,
+            also_known_as: resolved_did
+                .also_known_as
+                .iter()
+                .take(25)
+                .cloned()
+                .collect(),
+            document,
+        })
+    }
+
+    /// The handle listed in `alsoKnownAs`, without its `at://` prefix.
+    pub fn handle(&self) -> Option<&str> {
+        self.also_known_as
+            .iter()
+            .find_map(|value| value.strip_prefix("at://"))
+    }
+}
+
+/// Fetches a DID document. Error statuses fail before the body is read, so that server errors are
+/// reported as such rather than as invalid documents.
+pub(crate) async fn fetch_did_document(
+    http_client: &reqwest::Client,
+    url: &str,
+) -> Result<DidDocument> {
+    let document: serde_json::Value = http_client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    DidDocument::from_value(document)
+}
+
+/// Resolves DIDs of a single DID method into DID documents.
+#[async_trait]
+pub trait DidMethodResolver: Send + Sync {
+    /// The method name, e.g. `plc` for `did:plc:` identifiers.
+    fn method(&self) -> &str;
+
+    async fn resolve(&self, did: &str) -> Result<DidDocument>;
+
+    /// The host contacted to resolve the DID, which resolutions are rate limited by.
+    /// DIDs resolved through a shared service, such as the PLC directory, have none.
+    fn target_host(&self, _did: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The set of DID method resolvers used to resolve subjects and identities.
+#[derive(Clone)]
+pub struct DidMethodResolvers(Arc<Vec<Arc<dyn DidMethodResolver>>>);
+
+impl DidMethodResolvers {
+    pub fn new(resolvers: Vec<Arc<dyn DidMethodResolver>>) -> Self {
+        Self(Arc::new(resolvers))
+    }
+
+    /// Returns resolvers for `did:plc`, `did:web`, `did:key` and `did:webvh`.
+    pub fn default_resolvers(http_client: &reqwest::Client, plc_hostname: &str) -> Self {
+        Self::new(vec![
+            Arc::new(PlcResolver::new(http_client, plc_hostname)),
+            Arc::new(WebResolver::new(http_client)),
+            Arc::new(KeyResolver),
+            Arc::new(WebVhResolver::new(http_client)),
+        ])
+    }
+
+    /// Adds a resolver, replacing any existing resolver for the same method.
+    pub fn with_resolver(self, resolver: Arc<dyn DidMethodResolver>) -> Self {
+        let mut resolvers = self
+            .0
+            .iter()
+            .filter(|existing| existing.method() != resolver.method())
+            .cloned()
+            .collect::<Vec<_>>();
+        resolvers.push(resolver);
+        Self::new(resolvers)
+    }
+
+    pub fn supports(&self, did: &str) -> bool {
+        did_method(did).is_some_and(|method| self.resolver_for(method).is_some())
+    }
+
+    pub async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        let method = did_method(did).ok_or(anyhow!("Invalid DID"))?;
+        let resolver = self
+            .resolver_for(method)
+            .ok_or(anyhow!("Unsupported DID method: {}", method))?;
+        resolver.resolve(did).await
+    }
+
+    /// The lowercase host contacted to resolve the DID, if any.
+    pub fn target_host(&self, did: &str) -> Option<String> {
+        self.resolver_for(did_method(did)?)?
+            .target_host(did)
+            .map(|host| host.to_lowercase())
+    }
+
+    fn resolver_for(&self, method: &str) -> Option<&Arc<dyn DidMethodResolver>> {
+        self.0.iter().find(|resolver| resolver.method() == method)
+    }
+}
+
+/// Returns the method name of a DID, or `None` if the value is not a DID.
+pub fn did_method(did: &str) -> Option<&str> {
+    let (method, identifier) = did.strip_prefix("did:")?.split_once(':')?;
+    if method.is_empty()
+        || !method
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+        || identifier.is_empty()
+    {
+        return None;
+    }
+    Some(method)
+}
+
 pub fn is_valid_hostname(hostname: &str) -> bool {
     fn is_valid_char(byte: u8) -> bool {
         byte.is_ascii_lowercase()
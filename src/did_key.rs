@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::did::{DidDocument, DidMethodResolver};
+
+/// Multicodec prefixes and key lengths for the key types accepted in `did:key` identifiers.
+const KEY_TYPES: [(&[u8], usize, bool); 5] = [
+    // ed25519-pub
+    (&[0xed, 0x01], 32, false),
+    // secp256k1-pub (compressed)
+    (&[0xe7, 0x01], 33, false),
+    // p256-pub (compressed)
+    (&[0x80, 0x24], 33, false),
+    // p384-pub (compressed)
+    (&[0x81, 0x24], 49, false),
+    // x25519-pub
+    (&[0xec, 0x01], 32, true),
+];
+
+/// Derives the DID document for a `did:key` identifier from the key itself.
+pub(crate) fn key_document(did: &str) -> Result<DidDocument> {
+    let multibase_key = did.strip_prefix("did:key:").ok_or(anyhow!("Invalid DID"))?;
+
+    let encoded_key = multibase_key
+        .strip_prefix('z')
+        .ok_or(anyhow!("Unsupported multibase encoding: {}", did))?;
+    let decoded_key = bs58::decode(encoded_key)
+        .into_vec()
+        .map_err(|err| anyhow!("Invalid did:key {}: {}", did, err))?;
+
+    let key_agreement = KEY_TYPES
+        .iter()
+        .find_map(|(prefix, key_length, key_agreement)| {
+            decoded_key
+                .strip_prefix(*prefix)
+                .filter(|key| key.len() == *key_length)
+                .map(|_| *key_agreement)
+        })
+        .ok_or(anyhow!("Unsupported key type: {}", did))?;
+
+    let verification_method_id = format!("{}#{}", did, multibase_key);
+    let verification_method = json!({
+        "id": verification_method_id,
+        "type": "Multikey",
+        "controller": did,
+        "publicKeyMultibase": multibase_key,
+    });
+
+    let document = if key_agreement {
+        json!({
+            "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+            "id": did,
+            "verificationMethod": [verification_method],
+            "keyAgreement": [verification_method_id],
+        })
+    } else {
+        json!({
+            "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+            "id": did,
+            "verificationMethod": [verification_method],
+            "authentication": [verification_method_id],
+            "assertionMethod": [verification_method_id],
+            "capabilityInvocation": [verification_method_id],
+            "capabilityDelegation": [verification_method_id],
+        })
+    };
+
+    DidDocument::from_value(document)
+}
+
+pub struct KeyResolver;
+
+#[async_trait]
+impl DidMethodResolver for KeyResolver {
+    fn method(&self) -> &str {
+        "key"
+    }
+
+    async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        key_document(did)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_keys_are_decoded() {
+        // Examples from the did:key specification.
+        for did in [
+            "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme",
+            "did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169",
+            "did:key:z82Lm1MpAkeJcix9K8TMiLd5NMAhnwkjjCBeWHXyu3U4oT2MVJJKXkcVBgjGhnLBn2Kaau9",
+        ] {
+            let document = key_document(did).unwrap();
+            assert_eq!(document.id, did);
+
+            let multibase_key = did.strip_prefix("did:key:").unwrap();
+            let method = &document.document["verificationMethod"][0];
+            assert_eq!(method["id"], format!("{}#{}", did, multibase_key));
+            assert_eq!(method["controller"], did);
+            assert_eq!(method["publicKeyMultibase"], multibase_key);
+            assert_eq!(document.document["authentication"][0], method["id"]);
+            assert!(document.document.get("keyAgreement").is_none());
+        }
+    }
+
+    #[test]
+    fn key_agreement_keys_are_decoded() {
+        let did = "did:key:z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F";
+        let document = key_document(did).unwrap();
+        let method_id = &document.document["verificationMethod"][0]["id"];
+        assert_eq!(document.document["keyAgreement"][0], *method_id);
+        assert!(document.document.get("authentication").is_none());
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        for did in [
+            // Not base58btc.
+            "did:key:u6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            // An ed25519 key missing its last byte.
+            "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2do",
+            // Not a key type prefix.
+            "did:key:z3mJ",
+            "did:web:example.com",
+        ] {
+            assert!(key_document(did).is_err(), "{} was accepted", did);
+        }
+    }
+}
@@ -1,23 +1,43 @@
 use anyhow::Result;
+use async_trait::async_trait;
 
-use crate::did::ResolveDid;
+use crate::{
+    did::{fetch_did_document, DidDocument, DidMethodResolver},
+    monitoring::record_did_fetch,
+};
 
+#[tracing::instrument(skip(http_client))]
 pub(crate) async fn plc_query(
     http_client: &reqwest::Client,
     plc_hostname: &str,
     did: &str,
-) -> Result<(String, Vec<String>)> {
+) -> Result<DidDocument> {
     let url = format!("https://{}/{}", plc_hostname, did);
 
-    let resolved_did: ResolveDid = http_client.get(url).send().await?.json().await?;
+    record_did_fetch("plc", fetch_did_document(http_client, &url)).await
+}
+
+pub struct PlcResolver {
+    http_client: reqwest::Client,
+    plc_hostname: String,
+}
+
+impl PlcResolver {
+    pub fn new(http_client: &reqwest::Client, plc_hostname: &str) -> Self {
+        Self {
+            http_client: http_client.clone(),
+            plc_hostname: plc_hostname.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl DidMethodResolver for PlcResolver {
+    fn method(&self) -> &str {
+        "plc"
+    }
 
-    Ok((
-        resolved_did.id,
-        resolved_did
-            .also_known_as
-            .iter()
-            .take(25)
-            .cloned()
-            .collect(),
-    ))
+    async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        plc_query(&self.http_client, &self.plc_hostname, did).await
+    }
 }
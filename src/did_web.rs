@@ -1,11 +1,22 @@
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 
-use crate::did::ResolveDid;
+use crate::{
+    did::{fetch_did_document, DidDocument, DidMethodResolver, ResolveDid},
+    monitoring::record_did_fetch,
+};
 
-pub(crate) async fn web_query(
-    http_client: &reqwest::Client,
-    did: &str,
-) -> Result<(String, Vec<String>)> {
+/// The host of a `did:web` DID, without its port.
+pub(crate) fn did_web_host(did: &str) -> Option<String> {
+    let id = did.strip_prefix("did:web:")?;
+    id.split([':', '%'])
+        .next()
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+}
+
+#[tracing::instrument(skip(http_client))]
+pub(crate) async fn web_query(http_client: &reqwest::Client, did: &str) -> Result<DidDocument> {
     let parts = did
         .strip_prefix("did:web:")
         .map(|trimmed| trimmed.split(":").collect::<Vec<&str>>());
@@ -27,24 +38,48 @@ pub(crate) async fn web_query(
         format!("https://{}/{}/did.json", hostname, parts.join("/"))
     };
 
-    let resolved_did: ResolveDid = http_client.get(url).send().await?.json().await?;
-
-    Ok((
-        resolved_did.id,
-        resolved_did
-            .also_known_as
-            .iter()
-            .take(25)
-            .cloned()
-            .collect(),
-    ))
+    record_did_fetch("web", fetch_did_document(http_client, &url)).await
 }
 
+#[tracing::instrument(skip(http_client))]
 pub(crate) async fn web_query_simple(
     http_client: &reqwest::Client,
     hostname: &str,
 ) -> Result<String> {
     let url = format!("https://{}/.well-known/did.json", hostname);
-    let resolved_did: ResolveDid = http_client.get(url).send().await?.json().await?;
+    let resolved_did: ResolveDid = http_client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
     Ok(resolved_did.id)
 }
+
+pub struct WebResolver {
+    http_client: reqwest::Client,
+}
+
+impl WebResolver {
+    pub fn new(http_client: &reqwest::Client) -> Self {
+        Self {
+            http_client: http_client.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DidMethodResolver for WebResolver {
+    fn method(&self) -> &str {
+        "web"
+    }
+
+    async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        web_query(&self.http_client, did).await
+    }
+
+    fn target_host(&self, did: &str) -> Option<String> {
+        did_web_host(did)
+    }
+}
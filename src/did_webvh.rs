@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    did::{DidDocument, DidMethodResolver},
+    monitoring::record_did_fetch,
+};
+
+const SCID_PLACEHOLDER: &str = "{SCID}";
+
+/// Fetches and verifies the history log of a `did:webvh` DID, returning the current document.
+///
+/// The SCID and the entry hash chain are verified. Entry proofs are not checked.
+#[tracing::instrument(skip(http_client))]
+pub(crate) async fn webvh_query(http_client: &reqwest::Client, did: &str) -> Result<DidDocument> {
+    let (scid, url) = log_url(did)?;
+
+    let body = record_did_fetch("webvh", async {
+        Ok(http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    })
+    .await?;
+
+    let entries = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str::<Value>)
+        .collect::<Result<Vec<Value>, _>>()?;
+
+    let state = verify_log(&scid, &entries)?;
+
+    let document = DidDocument::from_value(state)?;
+    if document.id != did {
+        return Err(anyhow!("DID log does not describe {}", did));
+    }
+    Ok(document)
+}
+
+fn log_url(did: &str) -> Result<(String, String)> {
+    let mut parts = did
+        .strip_prefix("did:webvh:")
+        .ok_or(anyhow!("Invalid DID"))?
+        .split(':');
+
+    let scid = parts.next().filter(|value| !value.is_empty());
+    let hostname = parts.next().filter(|value| !value.is_empty());
+    let (scid, hostname) = match (scid, hostname) {
+        (Some(scid), Some(hostname)) => (scid, hostname.replace("%3A", ":")),
+        _ => return Err(anyhow!("Invalid DID")),
+    };
+    let path = parts.collect::<Vec<&str>>();
+
+    let url = if path.is_empty() {
+        format!("https://{}/.well-known/did.jsonl", hostname)
+    } else {
+        format!("https://{}/{}/did.jsonl", hostname, path.join("/"))
+    };
+    Ok((scid.to_string(), url))
+}
+
+fn verify_log(scid: &str, entries: &[Value]) -> Result<Value> {
+    let mut previous_version_id = scid.to_string();
+    let mut state = None;
+    let mut deactivated = false;
+
+    for (index, entry) in entries.iter().enumerate() {
+        let version_number = index + 1;
+        let version_id = entry
+            .get("versionId")
+            .and_then(Value::as_str)
+            .ok_or(anyhow!("DID log entry {} has no versionId", version_number))?;
+        let (entry_number, entry_hash) = version_id.split_once('-').ok_or(anyhow!(
+            "DID log entry {} has an invalid versionId",
+            version_number
+        ))?;
+        if entry_number.parse::<usize>().ok() != Some(version_number) {
+            return Err(anyhow!(
+                "DID log entry {} is out of sequence",
+                version_number
+            ));
+        }
+
+        let parameters = entry.get("parameters");
+        if index == 0 {
+            if parameters
+                .and_then(|value| value.get("scid"))
+                .and_then(Value::as_str)
+                != Some(scid)
+            {
+                return Err(anyhow!("DID log does not declare SCID {}", scid));
+            }
+            if scid_hash(entry, scid)? != scid {
+                return Err(anyhow!("DID log SCID does not match {}", scid));
+            }
+        }
+
+        if entry_hash != entry_hash_for(entry, &previous_version_id)? {
+            return Err(anyhow!("DID log entry {} hash is invalid", version_number));
+        }
+
+        if let Some(value) = parameters
+            .and_then(|value| value.get("deactivated"))
+            .and_then(Value::as_bool)
+        {
+            deactivated = value;
+        }
+        state = entry.get("state").cloned();
+        previous_version_id = version_id.to_string();
+    }
+
+    if deactivated {
+        return Err(anyhow!("DID has been deactivated"));
+    }
+    state.ok_or(anyhow!("DID log is empty"))
+}
+
+fn without_proof(entry: &Value, version_id: &str) -> Result<Value> {
+    let mut entry = entry.clone();
+    let fields = entry
+        .as_object_mut()
+        .ok_or(anyhow!("DID log entry is not an object"))?;
+    fields.remove("proof");
+    fields.insert(
+        "versionId".to_string(),
+        Value::String(version_id.to_string()),
+    );
+    Ok(entry)
+}
+
+fn entry_hash_for(entry: &Value, previous_version_id: &str) -> Result<String> {
+    Ok(multihash(&without_proof(entry, previous_version_id)?))
+}
+
+fn scid_hash(entry: &Value, scid: &str) -> Result<String> {
+    let preliminary = without_proof(entry, SCID_PLACEHOLDER)?
+        .to_string()
+        .replace(scid, SCID_PLACEHOLDER);
+    Ok(multihash(&serde_json::from_str(&preliminary)?))
+}
+
+/// Returns the base58btc encoded sha2-256 multihash of the canonical form of a value.
+fn multihash(value: &Value) -> String {
+    let mut canonical = String::new();
+    canonicalize(value, &mut canonical);
+    let digest = Sha256::digest(canonical.as_bytes());
+
+    let mut hash = vec![0x12, 0x20];
+    hash.extend_from_slice(&digest);
+    bs58::encode(hash).into_string()
+}
+
+/// Writes a value using the JSON Canonicalization Scheme (RFC 8785).
+fn canonicalize(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(fields) => {
+            let mut keys = fields.keys().collect::<Vec<&String>>();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                canonicalize(&fields[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                canonicalize(value, out);
+            }
+            out.push(']');
+        }
+        Value::Number(number) => match number.as_f64() {
+            Some(number) => canonicalize_number(number, out),
+            None => out.push_str(&number.to_string()),
+        },
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// Writes a number the way ECMAScript's `Number.prototype.toString` does, as RFC 8785 requires:
+/// integers up to 21 digits without an exponent, and the shortest digits that round trip.
+fn canonicalize_number(number: f64, out: &mut String) {
+    if number == 0.0 {
+        out.push('0');
+        return;
+    }
+    if number < 0.0 {
+        out.push('-');
+    }
+
+    // The shortest round trip digits, such as `1.5e-7`, as digits `15` and exponent `-7`.
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    let digit_count = digits.len() as i32;
+    // The position of the decimal point relative to the first digit.
+    let point = exponent.parse::<i32>().unwrap_or_default() + 1;
+
+    if digit_count <= point && point <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((point - digit_count) as usize));
+    } else if 0 < point && point <= 21 {
+        let (integer, fraction) = digits.split_at(point as usize);
+        out.push_str(integer);
+        out.push('.');
+        out.push_str(fraction);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-point as usize));
+        out.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(rest);
+        }
+        out.push('e');
+        out.push(if point > 0 { '+' } else { '-' });
+        out.push_str(&(point - 1).abs().to_string());
+    }
+}
+
+pub struct WebVhResolver {
+    http_client: reqwest::Client,
+}
+
+impl WebVhResolver {
+    pub fn new(http_client: &reqwest::Client) -> Self {
+        Self {
+            http_client: http_client.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DidMethodResolver for WebVhResolver {
+    fn method(&self) -> &str {
+        "webvh"
+    }
+
+    async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        webvh_query(&self.http_client, did).await
+    }
+
+    fn target_host(&self, did: &str) -> Option<String> {
+        let (_, url) = log_url(did).ok()?;
+        url::Url::parse(&url).ok()?.host_str().map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const SCID: &str = "Qmc9Hi5e65pTDDsDXbAoskcuermgYPuehJNtMSgUgeRsz7";
+
+    /// A two entry log of `did:webvh:<SCID>:domain.example`, hashed following the did:webvh 0.5
+    /// specification. The proofs are placeholders.
+    fn log_entries() -> Vec<Value> {
+        include_str!("../tests/fixtures/did_webvh.jsonl")
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn canonical(value: &Value) -> String {
+        let mut out = String::new();
+        canonicalize(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn canonical_form_matches_rfc_8785() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonical(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn numbers_are_serialized_like_ecmascript() {
+        for (number, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (5e-324, "5e-324"),
+            (-5e-324, "-5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (1e21, "1e+21"),
+            (1e23, "1e+23"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (4.5, "4.5"),
+        ] {
+            assert_eq!(canonical(&json!(number)), expected);
+        }
+        assert_eq!(canonical(&json!(-12)), "-12");
+    }
+
+    #[test]
+    fn logs_are_verified() {
+        let entries = log_entries();
+        assert_eq!(scid_hash(&entries[0], SCID).unwrap(), SCID);
+
+        let state = verify_log(SCID, &entries).unwrap();
+        assert_eq!(state, entries[1]["state"]);
+    }
+
+    #[test]
+    fn tampered_entries_are_rejected() {
+        for index in 0..2 {
+            let mut entries = log_entries();
+            entries[index]["state"]["alsoKnownAs"] = json!(["at://mallory.example"]);
+            let err = verify_log(SCID, &entries).unwrap_err();
+            assert!(err.to_string().contains("is invalid") || err.to_string().contains("SCID"));
+        }
+    }
+
+    #[test]
+    fn proofs_are_not_hashed() {
+        let mut entries = log_entries();
+        entries[1]["proof"] = json!([]);
+        assert!(verify_log(SCID, &entries).is_ok());
+    }
+
+    #[test]
+    fn incomplete_logs_are_rejected() {
+        let entries = log_entries();
+        assert!(verify_log(SCID, &entries[1..]).is_err());
+        assert!(verify_log("QmOtherScid", &entries).is_err());
+        assert!(verify_log(SCID, &[entries[1].clone(), entries[0].clone()]).is_err());
+        assert!(verify_log(SCID, &[]).is_err());
+    }
+
+    #[test]
+    fn deactivated_dids_are_rejected() {
+        let mut entries = log_entries();
+        entries[1]["parameters"] = json!({"deactivated": true});
+        // The hash of the changed entry no longer matches, so re-hash it.
+        let version_id = entry_hash_for(&entries[1], entries[0]["versionId"].as_str().unwrap());
+        entries[1]["versionId"] = json!(format!("2-{}", version_id.unwrap()));
+        let err = verify_log(SCID, &entries).unwrap_err();
+        assert_eq!(err.to_string(), "DID has been deactivated");
+    }
+}
@@ -1,7 +1,8 @@
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
 };
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
 
 #[derive(Debug)]
 pub struct WeatherVaneError(pub anyhow::Error);
@@ -17,9 +18,51 @@ where
 
 impl IntoResponse for WeatherVaneError {
     fn into_response(self) -> Response {
+        if self.0.is::<QueueFullError>() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(RETRY_AFTER, "5")],
+                self.0.to_string(),
+            )
+                .into_response();
+        }
         {
             tracing::error!(error = ?self.0, "internal server error");
             (StatusCode::INTERNAL_SERVER_ERROR).into_response()
         }
     }
 }
+
+/// The verification queue is full, so work was rejected rather than left waiting.
+#[derive(Debug, thiserror::Error)]
+#[error("The server is busy, try again shortly.")]
+pub struct QueueFullError;
+
+/// A lookup that failed for a transient reason, read back from a cache that keeps only its
+/// message.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct UnreachableError(pub String);
+
+/// Whether an error is likely to go away on its own, such as a timeout or a server error, rather
+/// than being a definitive answer.
+pub(crate) fn is_transient_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<UnreachableError>() {
+            return true;
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout()
+                || err.status().is_some_and(|status| {
+                    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                });
+        }
+        if let Some(err) = cause.downcast_ref::<ResolveError>() {
+            return matches!(
+                err.kind(),
+                ResolveErrorKind::Timeout | ResolveErrorKind::Io(_)
+            );
+        }
+        false
+    })
+}
@@ -0,0 +1,190 @@
+use hickory_resolver::{
+    config::{ResolverConfig, ResolverOpts},
+    AsyncResolver,
+};
+use moka::future::Cache;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::Sender;
+use utoipa::ToSchema;
+
+use crate::worker::QueueWork;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long the results of the PLC and DNS checks are reused, so that requests to the public
+/// readiness route don't each contact the PLC host.
+const UPSTREAM_CHECK_TTL: Duration = Duration::from_secs(5);
+
+/// The checks that decide readiness. The others are reported but only degrade verification, so
+/// a slow PLC host or a burst of visitors doesn't take the instance out of rotation.
+const REQUIRED_CHECKS: [&str; 2] = ["verify_task", "verify_queue"];
+
+/// Whether a background task is running. Clones share the same status.
+#[derive(Clone, Default)]
+pub struct TaskStatus(Arc<AtomicBool>);
+
+impl TaskStatus {
+    pub fn is_running(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Marks the task as running until the returned guard is dropped, including when the task
+    /// panics.
+    pub(crate) fn start(&self) -> RunningGuard {
+        self.0.store(true, Ordering::Relaxed);
+        RunningGuard(self.clone())
+    }
+}
+
+pub(crate) struct RunningGuard(TaskStatus);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        (self.0).0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CheckResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    /// Whether the verification task is running and its queue is open.
+    pub ready: bool,
+    pub checks: BTreeMap<&'static str, CheckResult>,
+}
+
+/// Checks the tasks and services needed to verify identities.
+///
+/// Only the task and queue checks decide readiness, and they run on every call. The PLC and DNS checks run at most once every
+/// `UPSTREAM_CHECK_TTL`, and concurrent calls wait for the same checks.
+#[derive(Clone)]
+pub struct ReadinessCheck {
+    http_client: reqwest::Client,
+    plc_hostname: String,
+    verify_work_tx: Sender<QueueWork>,
+    verify_task: TaskStatus,
+    upstream_checks: Cache<(), (CheckResult, CheckResult)>,
+}
+
+impl ReadinessCheck {
+    pub fn new(
+        http_client: &reqwest::Client,
+        plc_hostname: &str,
+        verify_work_tx: Sender<QueueWork>,
+        verify_task: TaskStatus,
+    ) -> Self {
+        Self {
+            http_client: http_client.clone(),
+            plc_hostname: plc_hostname.to_string(),
+            verify_work_tx,
+            verify_task,
+            upstream_checks: Cache::builder()
+                .max_capacity(1)
+                .time_to_live(UPSTREAM_CHECK_TTL)
+                .build(),
+        }
+    }
+
+    pub async fn check(&self) -> Readiness {
+        let (plc, dns) = self
+            .upstream_checks
+            .get_with((), async {
+                tokio::join!(timed(self.check_plc()), timed(self.check_dns()))
+            })
+            .await;
+
+        let mut checks = BTreeMap::new();
+        checks.insert("verify_task", immediate(self.check_verify_task()));
+        checks.insert("verify_queue", immediate(self.check_queue_open()));
+        checks.insert(
+            "verify_queue_capacity",
+            immediate(self.check_queue_capacity()),
+        );
+        checks.insert("plc", plc);
+        checks.insert("dns", dns);
+
+        Readiness {
+            ready: REQUIRED_CHECKS
+                .iter()
+                .all(|name| checks.get(name).is_some_and(|check| check.ok)),
+            checks,
+        }
+    }
+
+    fn check_verify_task(&self) -> Result<(), String> {
+        if !self.verify_task.is_running() {
+            return Err("the verification task is not running".to_string());
+        }
+        Ok(())
+    }
+
+    fn check_queue_open(&self) -> Result<(), String> {
+        if self.verify_work_tx.is_closed() {
+            return Err("the verification queue is closed".to_string());
+        }
+        Ok(())
+    }
+
+    fn check_queue_capacity(&self) -> Result<(), String> {
+        if self.verify_work_tx.capacity() == 0 {
+            return Err("the verification queue is full".to_string());
+        }
+        Ok(())
+    }
+
+    async fn check_plc(&self) -> Result<(), String> {
+        self.http_client
+            .get(format!("https://{}/_health", self.plc_hostname))
+            .timeout(CHECK_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    async fn check_dns(&self) -> Result<(), String> {
+        let resolver = AsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default());
+        match tokio::time::timeout(
+            CHECK_TIMEOUT,
+            resolver.lookup_ip(self.plc_hostname.as_str()),
+        )
+        .await
+        {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => Err(err.to_string()),
+            Err(_) => Err("DNS lookup timed out".to_string()),
+        }
+    }
+}
+
+fn immediate(result: Result<(), String>) -> CheckResult {
+    CheckResult {
+        ok: result.is_ok(),
+        error: result.err(),
+        duration_ms: 0,
+    }
+}
+
+async fn timed(check: impl std::future::Future<Output = Result<(), String>>) -> CheckResult {
+    let started = Instant::now();
+    let result = check.await;
+    CheckResult {
+        duration_ms: started.elapsed().as_millis(),
+        ..immediate(result)
+    }
+}
@@ -0,0 +1,99 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    response::{IntoResponse, Response},
+};
+use axum_template::RenderHtml;
+use fluent_bundle::FluentArgs;
+use http::{header::RETRY_AFTER, request::Parts, StatusCode};
+use minijinja::context as template_context;
+use std::{convert::Infallible, net::SocketAddr};
+
+use crate::{http::context::WebContext, i18n::message, rate_limit::RateLimiter};
+
+/// The IP address of the client, read from the trusted proxy header when one is configured.
+pub(crate) struct ClientIp(pub(crate) String);
+
+#[async_trait]
+impl FromRequestParts<WebContext> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        web_context: &WebContext,
+    ) -> Result<Self, Self::Rejection> {
+        // The proxy appends the address it received the request from, so the last value is the
+        // only one that cannot be set by the client.
+        let forwarded = web_context
+            .rate_limits
+            .trusted_proxy_header
+            .as_deref()
+            .and_then(|header| parts.headers.get(header))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        if let Some(forwarded) = forwarded {
+            return Ok(ClientIp(forwarded));
+        }
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_default();
+        Ok(ClientIp(peer))
+    }
+}
+
+/// Takes a token for the client, or returns the number of seconds until one is available.
+pub(crate) async fn limit_client(
+    limiter: Option<&RateLimiter>,
+    client_ip: &ClientIp,
+) -> Result<(), u64> {
+    limit_client_tokens(limiter, client_ip, 1).await
+}
+
+/// Takes `tokens` tokens for the client, or returns the number of seconds until they are
+/// available.
+pub(crate) async fn limit_client_tokens(
+    limiter: Option<&RateLimiter>,
+    client_ip: &ClientIp,
+    tokens: u32,
+) -> Result<(), u64> {
+    if let Some(limiter) = limiter {
+        if let Err(retry_after) = limiter.check_tokens(&client_ip.0, tokens).await {
+            let retry_after = (retry_after.as_secs_f64().ceil() as u64).max(1);
+            tracing::debug!(client_ip = client_ip.0, retry_after, "client rate limited");
+            return Err(retry_after);
+        }
+    }
+    Ok(())
+}
+
+/// The 429 page for a client that is making requests too quickly.
+pub(crate) fn too_many_requests(
+    web_context: &WebContext,
+    locale: &str,
+    retry_after: u64,
+) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, retry_after.to_string())],
+        RenderHtml(
+            "error.en-us.html",
+            web_context.engine.clone(),
+            template_context! {
+                locale,
+                message => too_many_requests_message(locale, retry_after),
+            },
+        ),
+    )
+        .into_response()
+}
+
+pub(crate) fn too_many_requests_message(locale: &str, retry_after: u64) -> String {
+    let mut args = FluentArgs::new();
+    args.set("seconds", retry_after);
+    message(locale, "error-too-many-requests", Some(&args))
+}
@@ -1,7 +1,8 @@
 use axum::extract::FromRef;
 use axum_template::engine::Engine;
+use metrics_exporter_prometheus::PrometheusHandle;
 use moka::future::Cache;
-use std::{ops::Deref, sync::Arc};
+use std::{ops::Deref, sync::Arc, time::Duration};
 
 #[cfg(feature = "reload")]
 use minijinja_autoreload::AutoReloader;
@@ -13,21 +14,35 @@ pub type AppEngine = Engine<AutoReloader>;
 use minijinja::Environment;
 
 use crate::{
-    cache::{DidDocumentResult, ResolveHandleResult},
-    worker::QueueWork,
+    config::Config,
+    health::ReadinessCheck,
+    rate_limit::{RateLimiter, RateLimits},
+    verifier::Verifier,
+    worker::{QueueProgress, QueueWork},
 };
 
 #[cfg(feature = "embed")]
 pub type AppEngine = Engine<Environment<'static>>;
 
+/// How often a visitor can ask for a DID to be checked again.
+pub(crate) const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct InnerWebContext {
     pub(crate) external_base: String,
     pub(crate) engine: AppEngine,
-    pub(crate) http_client: reqwest::Client,
     pub(crate) verify_work_tx: tokio::sync::mpsc::Sender<QueueWork>,
-    pub(crate) resolve_handle_cache: Cache<String, ResolveHandleResult>,
-    pub(crate) did_document_cache: Cache<String, DidDocumentResult>,
-    pub(crate) plc_hostname: String,
+    pub(crate) verifier: Verifier,
+    pub(crate) admin_token: Option<String>,
+    /// DIDs that were re-checked within the last `RECHECK_INTERVAL`.
+    pub(crate) recheck_limiter: Cache<String, ()>,
+    pub(crate) metrics_handle: PrometheusHandle,
+    pub(crate) readiness_check: ReadinessCheck,
+    pub(crate) queue_progress: QueueProgress,
+    pub(crate) rate_limits: RateLimits,
+    pub(crate) validate_limiter: Option<RateLimiter>,
+    pub(crate) updates_limiter: Option<RateLimiter>,
+    pub(crate) api_limiter: Option<RateLimiter>,
+    pub(crate) batch_limiter: Option<RateLimiter>,
 }
 
 #[derive(Clone, FromRef)]
@@ -43,22 +58,32 @@ impl Deref for WebContext {
 
 impl WebContext {
     pub fn new(
-        external_base: &str,
+        config: &Config,
         engine: AppEngine,
-        http_client: &reqwest::Client,
         verify_work_tx: tokio::sync::mpsc::Sender<QueueWork>,
-        resolve_handle_cache: Cache<String, ResolveHandleResult>,
-        did_document_cache: Cache<String, DidDocumentResult>,
-        plc_hostname: String,
+        verifier: Verifier,
+        metrics_handle: PrometheusHandle,
+        readiness_check: ReadinessCheck,
+        queue_progress: QueueProgress,
     ) -> Self {
         Self(Arc::new(InnerWebContext {
-            external_base: external_base.to_string(),
+            external_base: config.external_base.clone(),
             engine,
-            http_client: http_client.clone(),
             verify_work_tx,
-            resolve_handle_cache,
-            did_document_cache,
-            plc_hostname,
+            verifier,
+            admin_token: config.admin_token.clone(),
+            recheck_limiter: Cache::builder()
+                .max_capacity(1024 * 20)
+                .time_to_live(RECHECK_INTERVAL)
+                .build(),
+            metrics_handle,
+            readiness_check,
+            queue_progress,
+            rate_limits: config.rate_limits.clone(),
+            validate_limiter: config.rate_limits.validate.map(RateLimiter::new),
+            updates_limiter: config.rate_limits.updates.map(RateLimiter::new),
+            api_limiter: config.rate_limits.api.map(RateLimiter::new),
+            batch_limiter: config.rate_limits.batch.map(RateLimiter::new),
         }))
     }
 }
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::{
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    request::Parts,
+    StatusCode,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    errors::WeatherVaneError,
+    http::context::WebContext,
+    verifier::{CacheSizes, DidCacheEntries},
+    worker::verify_queued,
+};
+
+/// Requires the `ADMIN_TOKEN` bearer token. Admin routes are not found when no token is set.
+pub(crate) struct AdminAuth;
+
+#[async_trait]
+impl FromRequestParts<WebContext> for AdminAuth {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        web_context: &WebContext,
+    ) -> Result<Self, Self::Rejection> {
+        let admin_token = match web_context.admin_token.as_deref() {
+            Some(admin_token) => admin_token,
+            None => return Err(StatusCode::NOT_FOUND.into_response()),
+        };
+
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match token {
+            Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => {
+                Ok(AdminAuth)
+            }
+            _ => Err((StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response()),
+        }
+    }
+}
+
+/// Requires the admin token when `ADMIN_TOKEN` is set, and allows every request otherwise.
+pub(crate) struct AdminAuthIfConfigured;
+
+#[async_trait]
+impl FromRequestParts<WebContext> for AdminAuthIfConfigured {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        web_context: &WebContext,
+    ) -> Result<Self, Self::Rejection> {
+        if web_context.admin_token.is_none() {
+            return Ok(AdminAuthIfConfigured);
+        }
+        AdminAuth::from_request_parts(parts, web_context)
+            .await
+            .map(|_| AdminAuthIfConfigured)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct AdminCaches {
+    pub(crate) sizes: CacheSizes,
+    pub(crate) dids: Vec<DidCacheEntries>,
+}
+
+/// Lists the size of each cache and the cached entries of every DID.
+#[utoipa::path(
+    get,
+    path = "/admin/caches",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "The caches.", body = AdminCaches),
+        (status = 401, description = "The admin token is missing or wrong."),
+    )
+)]
+pub(crate) async fn handle_admin_caches(
+    _: AdminAuth,
+    State(web_context): State<WebContext>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    let sizes = web_context.verifier.cache_sizes().await;
+    let dids = web_context.verifier.all_cache_entries().await;
+    Ok(Json(AdminCaches { sizes, dids }))
+}
+
+/// Shows the cached entries of a DID.
+#[utoipa::path(
+    get,
+    path = "/admin/caches/{did}",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("did" = String, Path, description = "The DID.")),
+    responses(
+        (status = 200, description = "The cached entries of the DID.", body = DidCacheEntries),
+        (status = 401, description = "The admin token is missing or wrong."),
+    )
+)]
+pub(crate) async fn handle_admin_cache_did(
+    _: AdminAuth,
+    State(web_context): State<WebContext>,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    Ok(Json(web_context.verifier.cache_entries(&did).await))
+}
+
+/// Removes the cached entries of a DID.
+#[utoipa::path(
+    delete,
+    path = "/admin/caches/{did}",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("did" = String, Path, description = "The DID.")),
+    responses(
+        (status = 200, description = "The remaining cached entries of the DID.", body = DidCacheEntries),
+        (status = 401, description = "The admin token is missing or wrong."),
+    )
+)]
+pub(crate) async fn handle_admin_cache_purge(
+    _: AdminAuth,
+    State(web_context): State<WebContext>,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    web_context.verifier.invalidate_did(&did, None).await?;
+    Ok(Json(web_context.verifier.cache_entries(&did).await))
+}
+
+/// Removes the cached entries of a DID, then resolves the DID document and verifies each
+/// identity again.
+#[utoipa::path(
+    post,
+    path = "/admin/caches/{did}/refresh",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("did" = String, Path, description = "The DID.")),
+    responses(
+        (status = 200, description = "The refreshed cached entries of the DID.", body = DidCacheEntries),
+        (status = 401, description = "The admin token is missing or wrong."),
+        (status = 503, description = "The verification queue is full."),
+    )
+)]
+pub(crate) async fn handle_admin_cache_refresh(
+    _: AdminAuth,
+    State(web_context): State<WebContext>,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    web_context.verifier.invalidate_did(&did, None).await?;
+
+    if let Ok(document) = web_context.verifier.fetch_did_document(&did).await {
+        let identities = web_context.verifier.parse_identities(&document);
+        verify_queued(&web_context.verify_work_tx, &document.id, identities, true).await?;
+    }
+
+    Ok(Json(web_context.verifier.cache_entries(&did).await))
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use axum_template::TemplateEngine;
+use http::{
+    header::{CACHE_CONTROL, CONTENT_TYPE, RETRY_AFTER},
+    HeaderValue, StatusCode,
+};
+use minijinja::context as template_context;
+
+use crate::{
+    errors::{QueueFullError, WeatherVaneError},
+    http::{
+        client_limit::{limit_client, ClientIp},
+        context::WebContext,
+    },
+    worker::{verify_queued, VerifyWork},
+};
+
+const COLOR_VERIFIED: &str = "#4c1";
+const COLOR_PARTIAL: &str = "#dfb317";
+const COLOR_UNVERIFIED: &str = "#e05d44";
+const COLOR_UNKNOWN: &str = "#9f9f9f";
+
+/// Renders a badge summarizing how many identities of a DID are verified.
+#[utoipa::path(
+    get,
+    path = "/badge/{did}.svg",
+    tag = "badges",
+    params(("did" = String, Path, description = "The DID.")),
+    responses(
+        (status = 200, description = "The badge.", content_type = "image/svg+xml"),
+        (status = 429, description = "A \"rate limited\" badge.", content_type = "image/svg+xml"),
+    )
+)]
+pub(crate) async fn handle_badge(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    Path(did_slug): Path<String>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    if let Err(retry_after) = limit_client(web_context.api_limiter.as_ref(), &client_ip).await {
+        return render_rate_limited_badge(&web_context, "weathervane", retry_after);
+    }
+
+    let did = strip_svg(&did_slug);
+
+    let document = match web_context.verifier.fetch_did_document(did).await {
+        Ok(document) => document,
+        Err(_) => return render_badge(&web_context, "weathervane", "not found", COLOR_UNKNOWN),
+    };
+    let identities = web_context.verifier.parse_identities(&document);
+    if identities.is_empty() {
+        return render_badge(&web_context, "weathervane", "no identities", COLOR_UNKNOWN);
+    }
+    let total = identities.len();
+
+    let results =
+        match verify_queued(&web_context.verify_work_tx, &document.id, identities, false).await {
+            Ok(results) => results,
+            Err(err) if err.is::<QueueFullError>() => {
+                return render_badge(&web_context, "weathervane", "busy", COLOR_UNKNOWN)
+            }
+            Err(err) => return Err(err.into()),
+        };
+    let verified = results
+        .iter()
+        .filter(|work| matches!(work, VerifyWork::Ok(_)))
+        .count();
+
+    let color = if verified == total {
+        COLOR_VERIFIED
+    } else if verified > 0 {
+        COLOR_PARTIAL
+    } else {
+        COLOR_UNVERIFIED
+    };
+    render_badge(
+        &web_context,
+        "weathervane",
+        &format!("{}/{} verified", verified, total),
+        color,
+    )
+}
+
+/// Renders a badge showing whether a single identity of a DID is verified.
+///
+/// The identity is either the key used on the DID page or the `alsoKnownAs` value.
+#[utoipa::path(
+    get,
+    path = "/badge/{did}/{identity}.svg",
+    tag = "badges",
+    params(
+        ("did" = String, Path, description = "The DID."),
+        ("identity" = String, Path, description = "The identity key or the URL encoded `alsoKnownAs` value."),
+    ),
+    responses(
+        (status = 200, description = "The badge.", content_type = "image/svg+xml"),
+        (status = 429, description = "A \"rate limited\" badge.", content_type = "image/svg+xml"),
+    )
+)]
+pub(crate) async fn handle_badge_identity(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    Path((did, identity_slug)): Path<(String, String)>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    let identity_slug = strip_svg(&identity_slug);
+    if let Err(retry_after) = limit_client(web_context.api_limiter.as_ref(), &client_ip).await {
+        return render_rate_limited_badge(&web_context, identity_slug, retry_after);
+    }
+
+    let document = match web_context.verifier.fetch_did_document(&did).await {
+        Ok(document) => document,
+        Err(_) => return render_badge(&web_context, identity_slug, "not found", COLOR_UNKNOWN),
+    };
+
+    let parsed_identity = web_context
+        .verifier
+        .identity_validators()
+        .parse_identity(identity_slug);
+    let identity = web_context
+        .verifier
+        .parse_identities(&document)
+        .into_iter()
+        .find(|identity| identity.to_key() == identity_slug || identity == &parsed_identity);
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return render_badge(&web_context, identity_slug, "not listed", COLOR_UNKNOWN),
+    };
+
+    let results = match verify_queued(
+        &web_context.verify_work_tx,
+        &document.id,
+        vec![identity.clone()],
+        false,
+    )
+    .await
+    {
+        Ok(results) => results,
+        Err(err) if err.is::<QueueFullError>() => {
+            return render_badge(&web_context, &identity.value, "busy", COLOR_UNKNOWN)
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let (message, color) = match results.first() {
+        Some(VerifyWork::Ok(_)) => ("verified", COLOR_VERIFIED),
+        _ => ("unverified", COLOR_UNVERIFIED),
+    };
+    render_badge(&web_context, &identity.value, message, color)
+}
+
+fn strip_svg(value: &str) -> &str {
+    value.strip_suffix(".svg").unwrap_or(value)
+}
+
+fn text_width(value: &str) -> usize {
+    value.chars().count() * 7 + 10
+}
+
+fn render_badge(
+    web_context: &WebContext,
+    label: &str,
+    message: &str,
+    color: &str,
+) -> Result<axum::response::Response, WeatherVaneError> {
+    let label_width = text_width(label);
+    let message_width = text_width(message);
+    let rendered = web_context.engine.render(
+        "badge.en-us.svg",
+        template_context! {
+            label,
+            message,
+            color,
+            label_width,
+            message_width,
+            width => label_width + message_width,
+        },
+    )?;
+    Ok((
+        [
+            (CONTENT_TYPE, "image/svg+xml"),
+            (CACHE_CONTROL, "max-age=300"),
+        ],
+        rendered,
+    )
+        .into_response())
+}
+
+/// A badge for a client that is making requests too quickly, which is not cached.
+fn render_rate_limited_badge(
+    web_context: &WebContext,
+    label: &str,
+    retry_after: u64,
+) -> Result<axum::response::Response, WeatherVaneError> {
+    let mut response = render_badge(web_context, label, "rate limited", COLOR_UNKNOWN)?;
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+    let headers = response.headers_mut();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    Ok(response)
+}
@@ -1,51 +1,151 @@
 use anyhow::Result;
 use axum::{
     extract::{Path, State},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
+    Json,
 };
 use axum_htmx::HxRequest;
 use axum_template::RenderHtml;
+use http::{
+    header::{ACCEPT, CONTENT_TYPE, VARY},
+    HeaderMap, StatusCode,
+};
 use minijinja::context as template_context;
 
 use crate::{
-    cache::did_document_cached,
     errors::WeatherVaneError,
-    http::{context::WebContext, view_identity::IdentityView},
-    identity::parse_identities,
+    http::{
+        client_limit::{limit_client, ClientIp},
+        context::WebContext,
+        handle_xrpc::{GetVerificationsOutput, XrpcError, XrpcErrorBody},
+        negotiate::{quality_values, Locale},
+        view_identity::IdentityView,
+    },
+    worker::verify_queued,
 };
 
+/// The representations of a DID that `/did/:did` can respond with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DidFormat {
+    /// The DID page, or the DID page content for htmx requests.
+    Html,
+    /// The verification result of each identity.
+    Json,
+    /// The resolved DID document.
+    DidJson,
+}
+
+impl DidFormat {
+    fn for_media_range(media_range: &str) -> Option<Self> {
+        match media_range {
+            "text/html" | "text/*" | "*/*" => Some(Self::Html),
+            "application/json" | "application/*" => Some(Self::Json),
+            "application/did+json" => Some(Self::DidJson),
+            _ => None,
+        }
+    }
+
+    /// Picks the format with the highest quality in an `Accept` header, preferring specific media
+    /// types over wildcards and then earlier media ranges on ties. HTML is used when there is no
+    /// `Accept` header.
+    fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let accept = match headers.get(ACCEPT).and_then(|value| value.to_str().ok()) {
+            Some(accept) if !accept.trim().is_empty() => accept,
+            _ => return Some(Self::Html),
+        };
+
+        let mut best: Option<((f32, u8), Self)> = None;
+        for (media_type, quality) in quality_values(accept) {
+            let specificity = match media_type.as_str() {
+                "*/*" => 0,
+                media_type if media_type.ends_with("/*") => 1,
+                _ => 2,
+            };
+            if let Some(format) = Self::for_media_range(&media_type) {
+                let rank = (quality, specificity);
+                if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                    best = Some((rank, format));
+                }
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+}
+
+/// Shows the identities of a DID as HTML, as JSON verification results with
+/// `Accept: application/json`, or as the resolved DID document with
+/// `Accept: application/did+json`.
+#[utoipa::path(
+    get,
+    path = "/did/{did}",
+    tag = "pages",
+    params(
+        ("did" = String, Path, description = "The DID."),
+        ("Accept" = Option<String>, Header, description = "`text/html`, `application/json` or `application/did+json`."),
+    ),
+    responses(
+        (status = 200, description = "The DID page, the verification of each identity or the DID document.", content(
+            (String = "text/html"),
+            (GetVerificationsOutput = "application/json"),
+            (serde_json::Value = "application/did+json"),
+        )),
+        (status = 400, description = "`DidNotFound`, for JSON requests.", body = XrpcErrorBody),
+        (status = 406, description = "None of the accepted media types are supported."),
+        (status = 429, description = "`RateLimitExceeded`, for JSON requests.", body = XrpcErrorBody),
+        (status = 502, description = "`UpstreamFailure`, for JSON requests.", body = XrpcErrorBody),
+        (status = 503, description = "`ServiceUnavailable`, for JSON requests when the verification queue is full.", body = XrpcErrorBody),
+    )
+)]
 pub(crate) async fn handle_did(
     State(web_context): State<WebContext>,
     HxRequest(hx_request): HxRequest,
+    Locale(locale): Locale,
+    client_ip: ClientIp,
     Path(did_slug): Path<String>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, WeatherVaneError> {
-    let query_results = did_document_cached(
-        web_context.did_document_cache.clone(),
-        &web_context.http_client,
-        &web_context.plc_hostname,
-        &did_slug,
-    )
-    .await;
+    let response = match DidFormat::negotiate(&headers) {
+        Some(DidFormat::Html) => render_html(&web_context, hx_request, locale, &did_slug).await,
+        Some(format) => render_json(&web_context, &client_ip, format, &did_slug)
+            .await
+            .unwrap_or_else(IntoResponse::into_response),
+        None => (
+            StatusCode::NOT_ACCEPTABLE,
+            "Supported media types are text/html, application/json and application/did+json.",
+        )
+            .into_response(),
+    };
+    Ok(([(VARY, "Accept")], response))
+}
+
+async fn render_html(
+    web_context: &WebContext,
+    hx_request: bool,
+    locale: &str,
+    did_slug: &str,
+) -> Response {
+    let query_results = web_context.verifier.fetch_did_document(did_slug).await;
 
     if let Err(err) = query_results {
-        return Ok(RenderHtml(
+        return RenderHtml(
             "error.en-us.html",
             web_context.engine.clone(),
             template_context! {
+                locale,
                 message => err.to_string(),
             },
         )
-        .into_response());
+        .into_response();
     }
-    let (did, identities) = query_results.unwrap();
-
-    let parsed_identities = parse_identities(&identities);
+    let document = query_results.unwrap();
+    let parsed_identities = web_context.verifier.parse_identities(&document);
+    let did = document.id;
 
     let identity_views: Vec<IdentityView> = parsed_identities
         .iter()
         .map(|identity| IdentityView {
             key: identity.to_key(),
-            value: identity.pending_string(),
+            value: identity.localized_pending_string(locale),
         })
         .collect();
 
@@ -54,13 +154,45 @@ pub(crate) async fn handle_did(
         false => "did.en-us.html",
     };
 
-    Ok(RenderHtml(
+    RenderHtml(
         template,
         web_context.engine.clone(),
         template_context! {
+            locale,
             did,
             identities => identity_views,
         },
     )
-    .into_response())
+    .into_response()
+}
+
+async fn render_json(
+    web_context: &WebContext,
+    client_ip: &ClientIp,
+    format: DidFormat,
+    did_slug: &str,
+) -> Result<Response, XrpcError> {
+    limit_client(web_context.api_limiter.as_ref(), client_ip)
+        .await
+        .map_err(XrpcError::rate_limited)?;
+
+    let document = web_context
+        .verifier
+        .fetch_did_document(did_slug)
+        .await
+        .map_err(|err| XrpcError::lookup_failed("DidNotFound", &err))?;
+
+    if format == DidFormat::DidJson {
+        return Ok((
+            [(CONTENT_TYPE, "application/did+json")],
+            Json(document.document),
+        )
+            .into_response());
+    }
+
+    let identities = web_context.verifier.parse_identities(&document);
+    let results = verify_queued(&web_context.verify_work_tx, &document.id, identities, false)
+        .await
+        .map_err(|err| XrpcError::verify_failed(&err))?;
+    Ok(Json(GetVerificationsOutput::from_results(document.id, results)).into_response())
 }
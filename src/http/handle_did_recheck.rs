@@ -0,0 +1,94 @@
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect},
+};
+use axum_template::RenderHtml;
+use fluent_bundle::FluentArgs;
+use http::StatusCode;
+use minijinja::context as template_context;
+
+use crate::{
+    did::did_method,
+    errors::WeatherVaneError,
+    http::{
+        client_limit::{limit_client, too_many_requests, ClientIp},
+        context::{WebContext, RECHECK_INTERVAL},
+        negotiate::Locale,
+    },
+    i18n::message,
+};
+
+/// Drops the cached results of a DID so the DID page verifies each identity again.
+///
+/// Each DID can be re-checked once every `RECHECK_INTERVAL`.
+#[utoipa::path(
+    post,
+    path = "/did/{did}/recheck",
+    tag = "pages",
+    params(("did" = String, Path, description = "The DID.")),
+    responses(
+        (status = 303, description = "Redirects to the DID page."),
+        (status = 400, description = "The DID is not valid.", content_type = "text/html"),
+        (status = 429, description = "The DID was re-checked recently, or the client is making requests too quickly.", content_type = "text/html"),
+    )
+)]
+pub(crate) async fn handle_did_recheck(
+    State(web_context): State<WebContext>,
+    Locale(locale): Locale,
+    client_ip: ClientIp,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    if let Err(retry_after) = limit_client(web_context.api_limiter.as_ref(), &client_ip).await {
+        return Ok(too_many_requests(&web_context, locale, retry_after));
+    }
+
+    // Only DIDs take an entry in the re-check limiter, so other paths can't fill it.
+    if did_method(&did).is_none() {
+        let mut args = FluentArgs::new();
+        args.set("did", did.as_str());
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            RenderHtml(
+                "error.en-us.html",
+                web_context.engine.clone(),
+                template_context! {
+                    locale,
+                    message => message(locale, "error-invalid-did", Some(&args)),
+                },
+            ),
+        )
+            .into_response());
+    }
+
+    let entry = web_context
+        .recheck_limiter
+        .entry(did.clone())
+        .or_insert(())
+        .await;
+    if !entry.is_fresh() {
+        let mut args = FluentArgs::new();
+        args.set("did", did.as_str());
+        args.set("seconds", RECHECK_INTERVAL.as_secs());
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            RenderHtml(
+                "error.en-us.html",
+                web_context.engine.clone(),
+                template_context! {
+                    locale,
+                    message => message(locale, "error-recheck-too-soon", Some(&args)),
+                },
+            ),
+        )
+            .into_response());
+    }
+
+    // The handle of the cached document is resolved again along with the DID.
+    let document = web_context.verifier.cached_did_document(&did).await;
+    let handle = document.as_ref().and_then(|document| document.handle());
+    web_context.verifier.invalidate_did(&did, handle).await?;
+
+    let did_slug = url::form_urlencoded::byte_serialize(did.as_bytes()).collect::<String>();
+    Ok(Redirect::to(&format!("/did/{}", did_slug)).into_response())
+}
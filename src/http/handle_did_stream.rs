@@ -2,112 +2,457 @@ use std::{convert::Infallible, time::Duration};
 
 use anyhow::Result;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::sse::{Event, KeepAlive, Sse},
     response::IntoResponse,
 };
 use axum_template::RenderHtml;
 use axum_template::TemplateEngine;
+use fluent_bundle::FluentArgs;
 use futures_util::stream::Stream;
+use http::{HeaderMap, StatusCode};
 use minijinja::context as template_context;
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    cache::did_document_cached,
-    errors::WeatherVaneError,
-    http::context::{AppEngine, WebContext},
-    identity::parse_identities,
-    worker::{QueueWork, VerifyWork},
+    errors::{QueueFullError, WeatherVaneError},
+    http::{
+        client_limit::{limit_client, too_many_requests, ClientIp},
+        context::{AppEngine, WebContext},
+        negotiate::Locale,
+    },
+    i18n::{message, Reason},
+    identity::Identity,
+    monitoring::ActiveStream,
+    remedy::{Remedy, TxtRecord},
+    worker::{queue_work, QueueProgress, VerifyWork},
 };
 
+#[derive(Deserialize, IntoParams)]
+pub(crate) struct StreamQuery {
+    /// `json` for JSON event data instead of HTML.
+    format: Option<String>,
+}
+
+/// Streams the verification of each identity of a DID as server-sent events.
+///
+/// The stream sends an `identity` event per identity, `progress` events while the request is
+/// queued and as identities are verified, a `summary` event with counts, and then a `done`
+/// event before it ends. Event data is HTML for the DID page, or JSON with `?format=json`.
+///
+/// The id of each `identity` event records the outcome of every identity so far, so a client
+/// that reconnects with `Last-Event-ID` only receives the identities it missed.
+#[utoipa::path(
+    get,
+    path = "/did/{did}/updates",
+    tag = "api",
+    params(
+        ("did" = String, Path, description = "The DID."),
+        StreamQuery,
+        ("Last-Event-ID" = Option<String>, Header, description = "The id of the last `identity` event received."),
+    ),
+    responses(
+        (status = 200, description = "`identity` (`IdentityEventView`), `progress` (`ProgressView`), `summary` (`SummaryView`) and `done` events.", content_type = "text/event-stream"),
+        (status = 204, description = "The `Last-Event-ID` has no identities left to verify, so the client should stop reconnecting."),
+        (status = 429, description = "The client is making requests too quickly.", content_type = "text/html"),
+    )
+)]
 pub(crate) async fn handle_did_stream(
     State(web_context): State<WebContext>,
     Path(did_slug): Path<String>,
+    Query(stream_query): Query<StreamQuery>,
+    headers: HeaderMap,
+    client_ip: ClientIp,
+    Locale(locale): Locale,
 ) -> Result<impl IntoResponse, WeatherVaneError> {
-    let query_results = did_document_cached(
-        web_context.did_document_cache.clone(),
-        &web_context.http_client,
-        &web_context.plc_hostname,
-        &did_slug,
-    )
-    .await;
+    if let Err(retry_after) = limit_client(web_context.updates_limiter.as_ref(), &client_ip).await {
+        return Ok(too_many_requests(&web_context, locale, retry_after));
+    }
+
+    let query_results = web_context.verifier.fetch_did_document(&did_slug).await;
 
     if let Err(err) = query_results {
         return Ok(RenderHtml(
             "error.en-us.html",
             web_context.engine.clone(),
             template_context! {
+                locale,
                 message => err.to_string(),
             },
         )
         .into_response());
     }
-    let (did, identities) = query_results.unwrap();
-    let parsed_identities = parse_identities(&identities);
+    let document = query_results.unwrap();
+    let parsed_identities = web_context.verifier.parse_identities(&document);
+
+    let format = match stream_query.format.as_deref() {
+        Some("json") => StreamFormat::Json,
+        _ => StreamFormat::Html {
+            engine: web_context.engine.clone(),
+            locale,
+            did: document.id.clone(),
+            handle: document.handle().map(str::to_string),
+        },
+    };
+    let did = document.id;
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+    let outcomes = Outcomes::parse(last_event_id, parsed_identities.len());
+
+    let pending_identities: Vec<Identity> = parsed_identities
+        .iter()
+        .zip(&outcomes.0)
+        .filter(|(_, outcome)| **outcome == Outcome::Pending)
+        .map(|(identity, _)| identity.clone())
+        .collect();
+    // An `EventSource` reconnects after the stream ends and stops when it gets a 204, so a client
+    // that resumes once every identity was sent isn't sent the summary again.
+    if pending_identities.is_empty() && last_event_id.is_some() {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+    if pending_identities.is_empty() {
+        let stream = event_stream(format, parsed_identities, outcomes, None);
+        return Ok(stream.into_response());
+    }
+
+    let rx = match queue_work(&web_context.verify_work_tx, &did, pending_identities, false) {
+        Ok(rx) => rx,
+        Err(err) if err.is::<QueueFullError>() => {
+            return Ok(busy_stream(format, outcomes).into_response());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let verify_work_tx = &web_context.verify_work_tx;
+    let position = QueuePosition {
+        queued: (verify_work_tx.max_capacity() - verify_work_tx.capacity()) as u64,
+        taken: web_context.queue_progress.taken(),
+        progress: web_context.queue_progress.clone(),
+    };
+
+    let stream = event_stream(format, parsed_identities, outcomes, Some((rx, position)));
+    Ok(stream.into_response())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pending,
+    Verified,
+    Unverified,
+}
+
+/// The outcome of each identity in document order, written as one character per identity: `-`
+/// pending, `v` verified and `x` not verified.
+struct Outcomes(Vec<Outcome>);
+
+impl Outcomes {
+    /// Reads the outcomes from a `Last-Event-ID`, starting over if it doesn't match the document.
+    fn parse(last_event_id: Option<&str>, total: usize) -> Self {
+        let parsed = last_event_id
+            .filter(|value| value.len() == total)
+            .and_then(|value| {
+                value
+                    .chars()
+                    .map(|outcome| match outcome {
+                        '-' => Some(Outcome::Pending),
+                        'v' => Some(Outcome::Verified),
+                        'x' => Some(Outcome::Unverified),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<Outcome>>>()
+            });
+        Self(parsed.unwrap_or_else(|| vec![Outcome::Pending; total]))
+    }
+
+    fn to_id(&self) -> String {
+        self.0
+            .iter()
+            .map(|outcome| match outcome {
+                Outcome::Pending => '-',
+                Outcome::Verified => 'v',
+                Outcome::Unverified => 'x',
+            })
+            .collect()
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.0.iter().filter(|value| **value == outcome).count()
+    }
+
+    fn progress(&self, queue_position: Option<u64>) -> ProgressView {
+        ProgressView {
+            completed: self.0.len() - self.count(Outcome::Pending),
+            total: self.0.len(),
+            queue_position,
+            busy: false,
+        }
+    }
+
+    fn summary(&self) -> SummaryView {
+        SummaryView {
+            total: self.0.len(),
+            verified: self.count(Outcome::Verified),
+            unverified: self.count(Outcome::Unverified),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct IdentityEventView<'a> {
+    pub(crate) key: String,
+    pub(crate) kind: &'a str,
+    pub(crate) label: &'a str,
+    pub(crate) value: &'a str,
+    pub(crate) verified: bool,
+    pub(crate) reason: Option<&'a str>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ProgressView {
+    pub(crate) completed: usize,
+    pub(crate) total: usize,
+    /// The position of the request in the verification queue while it is waiting.
+    pub(crate) queue_position: Option<u64>,
+    /// The verification queue is full, so the identities won't be verified.
+    pub(crate) busy: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct SummaryView {
+    pub(crate) total: usize,
+    pub(crate) verified: usize,
+    pub(crate) unverified: usize,
+}
+
+/// A remedy with its step in the locale of the page.
+#[derive(Serialize)]
+struct RemedyView {
+    step: String,
+    record: Option<TxtRecord>,
+    snippet: Option<String>,
+    found: Option<String>,
+    expected: Option<String>,
+}
+
+impl RemedyView {
+    fn new(remedy: Remedy, locale: &str) -> Self {
+        Self {
+            step: remedy.step.localize(locale),
+            record: remedy.record,
+            snippet: remedy.snippet,
+            found: remedy.found,
+            expected: remedy.expected,
+        }
+    }
+}
+
+enum StreamFormat {
+    /// HTML fragments for the DID page, in a locale.
+    Html {
+        engine: AppEngine,
+        locale: &'static str,
+        did: String,
+        /// The handle in the DID document, which GitHub remedies link to.
+        handle: Option<String>,
+    },
+    Json,
+}
+
+impl StreamFormat {
+    fn identity_event(&self, identity: &Identity, reason: Option<&Reason>) -> Event {
+        let data = match self {
+            StreamFormat::Html {
+                engine,
+                locale,
+                did,
+                handle,
+            } => {
+                let remedy = reason
+                    .and_then(|reason| {
+                        Remedy::for_failure(did, handle.as_deref(), identity, reason)
+                    })
+                    .map(|remedy| RemedyView::new(remedy, locale));
+                let context = template_context! {
+                    locale,
+                    identity => identity.localized_pending_string(locale),
+                    success => reason.is_none(),
+                    message => reason.map(|reason| reason.localize(locale)),
+                    remedy,
+                };
+                let rendered = engine
+                    .render("partial_key.en-us.html", context)
+                    .unwrap_or_else(|err| err.to_string());
+                // The DID page lists every identity, so each one is swapped out of band.
+                format!(
+                    "<li id=\"identity-{}\" hx-swap-oob=\"true\">{}</li>",
+                    identity.to_key(),
+                    rendered
+                )
+            }
+            StreamFormat::Json => {
+                let reason = reason.map(Reason::to_string);
+                json_data(&IdentityEventView {
+                    key: identity.to_key(),
+                    kind: &identity.kind,
+                    label: &identity.label,
+                    value: &identity.value,
+                    verified: reason.is_none(),
+                    reason: reason.as_deref(),
+                })
+            }
+        };
+        Event::default().event("identity").data(data)
+    }
+
+    fn progress_event(&self, progress: &ProgressView) -> Event {
+        let data = match self {
+            StreamFormat::Html { locale, .. } if progress.busy => {
+                message(locale, "progress-busy", None)
+            }
+            StreamFormat::Html { locale, .. } => {
+                let mut args = FluentArgs::new();
+                match progress.queue_position {
+                    Some(queue_position) => {
+                        args.set("position", queue_position);
+                        message(locale, "progress-queued", Some(&args))
+                    }
+                    None => {
+                        args.set("completed", progress.completed);
+                        args.set("total", progress.total);
+                        message(locale, "progress-checked", Some(&args))
+                    }
+                }
+            }
+            StreamFormat::Json => json_data(progress),
+        };
+        Event::default().event("progress").data(data)
+    }
+
+    fn summary_event(&self, summary: &SummaryView) -> Event {
+        let data = match self {
+            StreamFormat::Html { locale, .. } => {
+                let mut args = FluentArgs::new();
+                args.set("verified", summary.verified);
+                args.set("total", summary.total);
+                message(locale, "summary-verified", Some(&args))
+            }
+            StreamFormat::Json => json_data(summary),
+        };
+        Event::default().event("summary").data(data)
+    }
+
+    fn done_event(&self) -> Event {
+        Event::default().event("done").data("done")
+    }
+}
 
-    let (tx, rx) = mpsc::channel::<VerifyWork>(parsed_identities.len() + 1);
+fn json_data<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
 
-    web_context
-        .verify_work_tx
-        .send(QueueWork {
-            did,
-            tx,
-            identities: Vec::from_iter(parsed_identities),
-        })
-        .await?;
+/// How long an `EventSource` waits before reconnecting after the `done` event.
+const DONE_RETRY: Duration = Duration::from_secs(60 * 60);
 
-    let sse_resp = stream_maker(web_context.engine.clone(), rx).await;
+/// Where queued work is in the verification queue.
+struct QueuePosition {
+    /// The work waiting in the queue when this work was queued, including itself.
+    queued: u64,
+    /// How much work had been taken off the queue when this work was queued.
+    taken: u64,
+    progress: QueueProgress,
+}
 
-    Ok(sse_resp.into_response())
+impl QueuePosition {
+    /// The 1-based position in the queue, or 0 once the work has been taken off it.
+    fn current(&self) -> u64 {
+        let taken_since = self.progress.taken().saturating_sub(self.taken);
+        self.queued.saturating_sub(taken_since)
+    }
 }
 
-async fn stream_maker(
-    engine: AppEngine,
-    rx: mpsc::Receiver<VerifyWork>,
+/// Responds to a visitor when the verification queue is full.
+fn busy_stream(
+    format: StreamFormat,
+    outcomes: Outcomes,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let mut rx = rx;
     let stream = async_stream::stream! {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut progress = outcomes.progress(None);
+        progress.busy = true;
+        yield Ok(format.progress_event(&progress));
+        yield Ok(format.done_event());
+    };
+    Sse::new(stream)
+}
 
-        while let Some(res) = rx.recv().await {
+/// Sends the results of the queued work, or only the summary when every identity was already
+/// sent before the client reconnected.
+fn event_stream(
+    format: StreamFormat,
+    identities: Vec<Identity>,
+    mut outcomes: Outcomes,
+    queued: Option<(mpsc::Receiver<VerifyWork>, QueuePosition)>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = async_stream::stream! {
+        let _active_stream = ActiveStream::new();
 
-            if res == VerifyWork::Done() {
-                break;
-            }
+        if let Some((mut rx, position)) = queued {
+            let mut first_result = None;
+            let mut shown_position = 0;
+            let mut queue_interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + Duration::from_secs(1),
+                Duration::from_secs(1),
+            );
 
-            let (key, context) = match res {
-                VerifyWork::Ok(identity) => {
-                    (format!("identity-{}", identity.to_key()), template_context! { identity => identity.pending_string(), success => true })
-                },
-                VerifyWork::Error(identity, message) => {
-                    (format!("identity-{}", identity.to_key()), template_context! { identity => identity.pending_string(), success => false, message => message })
-                },
-                _ => unreachable!(),
-            };
-
-            let render_result = engine.render("partial_key.en-us.html", context);
-            match render_result {
-                Ok(rendered) => {
-                    let event =  Event::default().event(key).data(rendered);
-                    yield Ok(event);
-                },
-                Err(err) => {
-                    let event =  Event::default().event(key).data(err.to_string());
-                    yield Ok(event);
+            // While waiting for the first result, tell the client where it is in the queue.
+            while first_result.is_none() {
+                tokio::select! {
+                    res = rx.recv() => {
+                        match res {
+                            Some(res) => first_result = Some(res),
+                            None => break,
+                        }
+                    },
+                    _ = queue_interval.tick() => {
+                        let current_position = position.current();
+                        if current_position > 0 && current_position != shown_position {
+                            shown_position = current_position;
+                            yield Ok(format.progress_event(&outcomes.progress(Some(current_position))));
+                        }
+                    },
                 }
             }
 
-        }
+            while let Some(res) = match first_result.take() {
+                Some(res) => Some(res),
+                None => rx.recv().await,
+            } {
+                let (identity, reason) = match res.into_outcome() {
+                    Some(outcome) => outcome,
+                    None => break,
+                };
+                if let Some(index) = identities.iter().position(|value| *value == identity) {
+                    outcomes.0[index] = if reason.is_none() {
+                        Outcome::Verified
+                    } else {
+                        Outcome::Unverified
+                    };
+                }
 
-        rx.close();
+                yield Ok(format.identity_event(&identity, reason.as_ref()).id(outcomes.to_id()));
+                yield Ok(format.progress_event(&outcomes.progress(None)));
+            }
 
-        // Nick: The "done" event and payload is used to indicate that no further information will
-        // be sent. Clients should disconnect and not reconnect. For clients that misbehave, return
-        // an infinite loop.
-        loop {
-            interval.tick().await;
-            yield Ok(Event::default().event("done").data("done"));
+            rx.close();
         }
+
+        yield Ok(format.summary_event(&outcomes.summary()));
+        // Clients that reconnect without a `Last-Event-ID`, such as for a DID without identities,
+        // wait before verifying the DID again.
+        yield Ok(format.done_event().retry(DONE_RETRY));
     };
     Sse::new(stream).keep_alive(KeepAlive::default())
 }
@@ -3,15 +3,47 @@ use axum::{extract::State, response::IntoResponse};
 use axum_template::RenderHtml;
 use minijinja::context as template_context;
 
-use crate::{errors::WeatherVaneError, http::context::WebContext};
+use crate::{
+    errors::WeatherVaneError,
+    http::{context::WebContext, negotiate::Locale},
+    i18n::try_message,
+};
 
-pub async fn handle_guide(
+#[derive(serde::Serialize)]
+struct ValidatorView {
+    label: String,
+    guide: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/guide",
+    tag = "pages",
+    responses((status = 200, description = "How to link each kind of identity to a DID.", content_type = "text/html"))
+)]
+pub(crate) async fn handle_guide(
     State(web_context): State<WebContext>,
+    Locale(locale): Locale,
 ) -> Result<impl IntoResponse, WeatherVaneError> {
+    let validators: Vec<ValidatorView> = web_context
+        .verifier
+        .identity_validators()
+        .validators()
+        .map(|validator| ValidatorView {
+            label: try_message(locale, &format!("label-{}", validator.kind()), None)
+                .unwrap_or_else(|| validator.label().to_string()),
+            guide: try_message(locale, &format!("guide-{}", validator.kind()), None)
+                .unwrap_or_else(|| validator.guide().to_string()),
+        })
+        .collect();
+
     Ok(RenderHtml(
         "guide.en-us.html",
         web_context.engine.clone(),
-        template_context! {},
+        template_context! {
+            locale,
+            validators,
+        },
     )
     .into_response())
 }
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use axum::{extract::State, response::IntoResponse, Json};
+use http::StatusCode;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{errors::WeatherVaneError, health::Readiness, http::context::WebContext};
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct HealthStatus {
+    pub(crate) status: &'static str,
+}
+
+/// Reports that the process is alive.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "The process is running.", body = HealthStatus))
+)]
+pub(crate) async fn handle_healthz() -> Result<impl IntoResponse, WeatherVaneError> {
+    Ok(Json(HealthStatus { status: "ok" }))
+}
+
+/// Reports whether verification requests can be served, with the result of each check.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Identities can be verified.", body = Readiness),
+        (status = 503, description = "The verification task or its queue is unavailable.", body = Readiness),
+    )
+)]
+pub(crate) async fn handle_readyz(
+    State(web_context): State<WebContext>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    let readiness = web_context.readiness_check.check().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((status, Json(readiness)))
+}
@@ -4,21 +4,32 @@ use axum_template::RenderHtml;
 use minijinja::context as template_context;
 use serde::{Deserialize, Serialize};
 
-use crate::{errors::WeatherVaneError, http::context::WebContext};
+use crate::{
+    errors::WeatherVaneError,
+    http::{context::WebContext, negotiate::Locale},
+};
 
 #[derive(Deserialize, Serialize)]
 pub struct FormHints {
     pub query: Option<String>,
 }
 
-pub async fn handle_index(
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "pages",
+    responses((status = 200, description = "The home page.", content_type = "text/html"))
+)]
+pub(crate) async fn handle_index(
     State(web_context): State<WebContext>,
+    Locale(locale): Locale,
     Query(form_hints): Query<FormHints>,
 ) -> Result<impl IntoResponse, WeatherVaneError> {
     Ok(RenderHtml(
         "index.en-us.html",
         web_context.engine.clone(),
         template_context! {
+            locale,
             subject_value => form_hints.query,
         },
     )
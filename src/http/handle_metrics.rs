@@ -0,0 +1,35 @@
+use anyhow::Result;
+use axum::{extract::State, response::IntoResponse};
+use http::header::CONTENT_TYPE;
+
+use crate::{
+    errors::WeatherVaneError,
+    http::{context::WebContext, handle_admin::AdminAuthIfConfigured},
+    monitoring::{set_cache_entries, set_verify_queue_depth},
+};
+
+/// Renders metrics in the Prometheus text format. The admin token is required when one is
+/// configured.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    security((), ("admin_token" = [])),
+    responses(
+        (status = 200, description = "Prometheus metrics.", content_type = "text/plain"),
+        (status = 401, description = "`ADMIN_TOKEN` is set and the admin token is missing or wrong."),
+    )
+)]
+pub(crate) async fn handle_metrics(
+    _: AdminAuthIfConfigured,
+    State(web_context): State<WebContext>,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    let verify_work_tx = &web_context.verify_work_tx;
+    set_verify_queue_depth(verify_work_tx.max_capacity() - verify_work_tx.capacity());
+    set_cache_entries(&web_context.verifier.cache_sizes().await);
+
+    Ok((
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        web_context.metrics_handle.render(),
+    ))
+}
@@ -5,16 +5,34 @@ use axum_template::RenderHtml;
 use http::StatusCode;
 use minijinja::context as template_context;
 
-use crate::{cache::resolve_subject_cached, errors::WeatherVaneError, http::context::WebContext};
+use crate::{
+    errors::WeatherVaneError,
+    http::{
+        client_limit::{limit_client, too_many_requests_message, ClientIp},
+        context::WebContext,
+        negotiate::Locale,
+    },
+    i18n::message,
+};
 
 #[derive(serde::Deserialize)]
 pub(crate) struct ValidateForm {
     subject: String,
 }
 
+/// Resolves the subject of the home page form and redirects to its DID page with htmx.
+#[utoipa::path(
+    post,
+    path = "/validate",
+    tag = "pages",
+    request_body(content = String, description = "A `subject` form field with a handle or DID.", content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, description = "An `HX-Redirect` to the DID page, or the form with an error.", content_type = "text/html"))
+)]
 pub(crate) async fn handle_validate(
     State(web_context): State<WebContext>,
     HxRequest(hx_request): HxRequest,
+    client_ip: ClientIp,
+    Locale(locale): Locale,
     Form(web_form): Form<ValidateForm>,
 ) -> Result<impl IntoResponse, WeatherVaneError> {
     if !hx_request {
@@ -22,23 +40,38 @@ pub(crate) async fn handle_validate(
             "error.en-us.html",
             web_context.engine.clone(),
             template_context! {
-                message => "Invalid Request",
+                locale,
+                message => message(locale, "error-invalid-request", None),
             },
         )
         .into_response());
     }
 
-    let resolved_did = resolve_subject_cached(
-        web_context.resolve_handle_cache.clone(),
-        &web_context.http_client,
-        &web_form.subject,
-    )
-    .await;
+    // htmx only swaps successful responses, so the limit is shown next to the form.
+    if let Err(retry_after) = limit_client(web_context.validate_limiter.as_ref(), &client_ip).await
+    {
+        return Ok(RenderHtml(
+            "partial_validate.en-us.html",
+            web_context.engine.clone(),
+            template_context! {
+                locale,
+                subject_value => web_form.subject,
+                subject_error => too_many_requests_message(locale, retry_after),
+            },
+        )
+        .into_response());
+    }
+
+    let resolved_did = web_context
+        .verifier
+        .resolve_subject(&web_form.subject)
+        .await;
     if let Err(err) = resolved_did {
         return Ok(RenderHtml(
             "partial_validate.en-us.html",
             web_context.engine.clone(),
             template_context! {
+                locale,
                 subject_value => web_form.subject,
                 subject_error => err.to_string(),
             },
@@ -53,7 +86,10 @@ pub(crate) async fn handle_validate(
         return Ok(RenderHtml(
             "error.en-us.html",
             web_context.engine.clone(),
-            template_context! { message => "Internal Server Error" },
+            template_context! {
+                locale,
+                message => message(locale, "error-internal", None),
+            },
         )
         .into_response());
     }
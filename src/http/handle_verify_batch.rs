@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    response::IntoResponse,
+};
+use futures_util::stream::{self, StreamExt};
+use http::{header::CONTENT_TYPE, HeaderMap};
+use ordermap::OrderSet;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    errors::WeatherVaneError,
+    http::{
+        client_limit::{limit_client_tokens, ClientIp},
+        context::WebContext,
+        handle_xrpc::{XrpcError, XrpcErrorBody},
+    },
+    identity::Identity,
+    worker::{verify_queued, VerifyWork},
+};
+
+const MAX_BATCH_SUBJECTS: usize = 500;
+const BATCH_CONCURRENCY: usize = 8;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+pub(crate) enum BatchRequest {
+    Subjects(Vec<String>),
+    Object { subjects: Vec<String> },
+}
+
+/// A line of the NDJSON batch response. Identity lines for a subject are followed by a subject line.
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum BatchResult {
+    Identity {
+        subject: String,
+        did: String,
+        identity: Identity,
+        verified: bool,
+        reason: Option<String>,
+    },
+    Subject {
+        subject: String,
+        did: Option<String>,
+        verified: bool,
+        error: Option<String>,
+        identities: usize,
+    },
+}
+
+/// Verifies a list of handles and DIDs, given as a JSON array or as newline separated text.
+#[utoipa::path(
+    post,
+    path = "/api/verify/batch",
+    tag = "api",
+    request_body(
+        description = "Up to 500 handles or DIDs.",
+        content(
+            (BatchRequest = "application/json"),
+            (String = "text/plain"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "One line per identity and per subject.", body = BatchResult, content_type = "application/x-ndjson"),
+        (status = 400, description = "`InvalidRequest`, when the request has no subjects or too many.", body = XrpcErrorBody),
+        (status = 429, description = "`RateLimitExceeded`, when the client verified too many subjects recently.", body = XrpcErrorBody),
+    )
+)]
+pub(crate) async fn handle_verify_batch(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, WeatherVaneError> {
+    let subjects = match parse_subjects(&headers, &body) {
+        Ok(subjects) => subjects,
+        Err(err) => return Ok(XrpcError::invalid_request(&err.to_string()).into_response()),
+    };
+
+    // Each subject is looked up and verified, so each one counts against the client's limit.
+    let tokens = subjects.len() as u32;
+    if let Err(retry_after) =
+        limit_client_tokens(web_context.batch_limiter.as_ref(), &client_ip, tokens).await
+    {
+        return Ok(XrpcError::rate_limited(retry_after).into_response());
+    }
+
+    let lines = stream::iter(subjects)
+        .map(move |subject| verify_subject(web_context.clone(), subject))
+        .buffer_unordered(BATCH_CONCURRENCY)
+        .flat_map(stream::iter)
+        .map(|result| serde_json::to_string(&result).map(|line| line + "\n"));
+
+    Ok((
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+fn parse_subjects(headers: &HeaderMap, body: &Bytes) -> Result<Vec<String>> {
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+
+    let subjects = if is_json {
+        match serde_json::from_slice::<BatchRequest>(body)? {
+            BatchRequest::Subjects(subjects) | BatchRequest::Object { subjects } => subjects,
+        }
+    } else {
+        std::str::from_utf8(body)?
+            .lines()
+            .map(|line| line.to_string())
+            .collect()
+    };
+
+    let subjects = subjects
+        .iter()
+        .map(|subject| subject.trim().to_string())
+        .filter(|subject| !subject.is_empty())
+        .collect::<OrderSet<String>>();
+
+    if subjects.is_empty() {
+        return Err(anyhow!("No subjects given"));
+    }
+    if subjects.len() > MAX_BATCH_SUBJECTS {
+        return Err(anyhow!(
+            "Too many subjects, at most {} are allowed",
+            MAX_BATCH_SUBJECTS
+        ));
+    }
+    Ok(Vec::from_iter(subjects))
+}
+
+async fn verify_subject(web_context: WebContext, subject: String) -> Vec<BatchResult> {
+    let subject_error = |did: Option<String>, err: anyhow::Error| {
+        vec![BatchResult::Subject {
+            subject: subject.clone(),
+            did,
+            verified: false,
+            error: Some(err.to_string()),
+            identities: 0,
+        }]
+    };
+
+    let did = match web_context.verifier.resolve_subject(&subject).await {
+        Ok(did) => did,
+        Err(err) => return subject_error(None, err),
+    };
+
+    let document = match web_context.verifier.fetch_did_document(&did).await {
+        Ok(document) => document,
+        Err(err) => return subject_error(Some(did), err),
+    };
+    let identities = web_context.verifier.parse_identities(&document);
+    let identity_count = identities.len();
+
+    let verify_results =
+        match verify_queued(&web_context.verify_work_tx, &document.id, identities, false).await {
+            Ok(verify_results) => verify_results,
+            Err(err) => return subject_error(Some(document.id), err),
+        };
+
+    let mut results = Vec::with_capacity(identity_count + 1);
+    let mut verified = true;
+    for (identity, reason) in verify_results
+        .into_iter()
+        .filter_map(VerifyWork::into_outcome)
+    {
+        verified &= reason.is_none();
+        results.push(BatchResult::Identity {
+            subject: subject.clone(),
+            did: document.id.clone(),
+            identity,
+            verified: reason.is_none(),
+            reason: reason.map(|reason| reason.to_string()),
+        });
+    }
+
+    results.push(BatchResult::Subject {
+        subject,
+        did: Some(document.id),
+        verified,
+        error: None,
+        identities: identity_count,
+    });
+    results
+}
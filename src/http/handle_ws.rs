@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::IntoResponse,
+};
+use fluent_bundle::FluentArgs;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::mpsc,
+    task::{AbortHandle, Id, JoinSet},
+};
+use utoipa::ToSchema;
+
+use crate::{
+    errors::QueueFullError,
+    http::{
+        client_limit::{limit_client, too_many_requests_message, ClientIp},
+        context::{WebContext, RECHECK_INTERVAL},
+    },
+    i18n::{message, DEFAULT_LOCALE},
+    worker::queue_work,
+};
+
+/// How many DIDs a connection can be verifying at once.
+const MAX_SUBSCRIPTIONS: usize = 32;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ClientMessage {
+    /// Verifies the identities of a DID.
+    Subscribe { did: String },
+    /// Stops verifying the identities of a DID.
+    Unsubscribe { did: String },
+    /// Drops the cached results of a DID and verifies its identities again.
+    Reverify { did: String },
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ServerMessage {
+    Subscribed {
+        did: String,
+        identities: usize,
+    },
+    Identity {
+        did: String,
+        key: String,
+        kind: String,
+        label: String,
+        value: String,
+        verified: bool,
+        reason: Option<String>,
+    },
+    Done {
+        did: String,
+    },
+    Error {
+        did: Option<String>,
+        message: String,
+    },
+}
+
+/// Verifies the identities of any number of DIDs over one WebSocket connection.
+///
+/// Clients send `ClientMessage` and receive `ServerMessage` JSON text messages.
+#[utoipa::path(
+    get,
+    path = "/api/ws",
+    tag = "api",
+    responses((status = 101, description = "Switches to the WebSocket protocol."))
+)]
+pub(crate) async fn handle_ws(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, web_context, client_ip))
+}
+
+async fn handle_socket(socket: WebSocket, web_context: WebContext, client_ip: ClientIp) {
+    let (mut sink, mut stream) = socket.split();
+    let (out_tx, mut out_rx) = mpsc::channel::<ServerMessage>(64);
+
+    // Dropping the set when the connection closes aborts every subscription, which stops any
+    // verification still in progress.
+    let mut subscriptions = JoinSet::new();
+    // The active subscription of each DID, and the DID of each task in the set. Finished and
+    // aborted tasks are removed as the set reports them.
+    let mut subscription_handles: HashMap<String, AbortHandle> = HashMap::new();
+    let mut subscription_dids: HashMap<Id, String> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                let client_message = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(client_message) => client_message,
+                    Err(err) => {
+                        let _ = out_tx.try_send(ServerMessage::Error {
+                            did: None,
+                            message: format!("Invalid message: {}", err),
+                        });
+                        continue;
+                    }
+                };
+
+                let (did, refresh) = match client_message {
+                    ClientMessage::Subscribe { did } => (did, false),
+                    ClientMessage::Reverify { did } => (did, true),
+                    ClientMessage::Unsubscribe { did } => {
+                        if let Some(handle) = subscription_handles.remove(&did) {
+                            handle.abort();
+                        }
+                        continue;
+                    }
+                };
+
+                if let Some(handle) = subscription_handles.remove(&did) {
+                    handle.abort();
+                }
+                if subscription_handles.len() >= MAX_SUBSCRIPTIONS {
+                    let _ = out_tx.try_send(ServerMessage::Error {
+                        did: Some(did),
+                        message: format!(
+                            "A connection can verify at most {} DIDs at once.",
+                            MAX_SUBSCRIPTIONS
+                        ),
+                    });
+                    continue;
+                }
+                if let Err(retry_after) =
+                    limit_client(web_context.updates_limiter.as_ref(), &client_ip).await
+                {
+                    let _ = out_tx.try_send(ServerMessage::Error {
+                        did: Some(did),
+                        message: too_many_requests_message(DEFAULT_LOCALE, retry_after),
+                    });
+                    continue;
+                }
+
+                let handle = subscriptions.spawn(subscribe(
+                    web_context.clone(),
+                    did.clone(),
+                    refresh,
+                    out_tx.clone(),
+                ));
+                subscription_dids.insert(handle.id(), did.clone());
+                subscription_handles.insert(did, handle);
+            },
+            Some(message) = out_rx.recv() => {
+                let text = match serde_json::to_string(&message) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        tracing::error!(error = ?err, "serializing websocket message failed");
+                        continue;
+                    }
+                };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            },
+            Some(joined) = subscriptions.join_next_with_id() => {
+                let id = match joined {
+                    Ok((id, ())) => id,
+                    Err(err) => err.id(),
+                };
+                // A re-subscription replaces the handle of its DID before the aborted task is
+                // reported, so only remove the handle of the task that finished.
+                if let Some(did) = subscription_dids.remove(&id) {
+                    if subscription_handles.get(&did).is_some_and(|handle| handle.id() == id) {
+                        subscription_handles.remove(&did);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Queues verification of the identities of a DID and forwards each result to the connection.
+async fn subscribe(
+    web_context: WebContext,
+    did: String,
+    refresh: bool,
+    out_tx: mpsc::Sender<ServerMessage>,
+) {
+    let error = |message: String| ServerMessage::Error {
+        did: Some(did.clone()),
+        message,
+    };
+
+    if refresh {
+        let entry = web_context
+            .recheck_limiter
+            .entry(did.clone())
+            .or_insert(())
+            .await;
+        if !entry.is_fresh() {
+            let mut args = FluentArgs::new();
+            args.set("did", did.as_str());
+            args.set("seconds", RECHECK_INTERVAL.as_secs());
+            let _ = out_tx
+                .send(error(message(
+                    DEFAULT_LOCALE,
+                    "error-recheck-too-soon",
+                    Some(&args),
+                )))
+                .await;
+            return;
+        }
+        if let Err(err) = web_context.verifier.invalidate_did(&did, None).await {
+            let _ = out_tx.send(error(err.to_string())).await;
+            return;
+        }
+    }
+
+    let document = match web_context.verifier.fetch_did_document(&did).await {
+        Ok(document) => document,
+        Err(err) => {
+            let _ = out_tx.send(error(err.to_string())).await;
+            return;
+        }
+    };
+    let identities = web_context.verifier.parse_identities(&document);
+
+    let _ = out_tx
+        .send(ServerMessage::Subscribed {
+            did: did.clone(),
+            identities: identities.len(),
+        })
+        .await;
+
+    let mut rx = match queue_work(&web_context.verify_work_tx, &document.id, identities, false) {
+        Ok(rx) => rx,
+        Err(err) if err.is::<QueueFullError>() => {
+            let _ = out_tx
+                .send(error(message(DEFAULT_LOCALE, "progress-busy", None)))
+                .await;
+            return;
+        }
+        Err(err) => {
+            let _ = out_tx.send(error(err.to_string())).await;
+            return;
+        }
+    };
+
+    while let Some(work) = rx.recv().await {
+        let (identity, reason) = match work.into_outcome() {
+            Some(outcome) => outcome,
+            None => break,
+        };
+        let message = ServerMessage::Identity {
+            did: did.clone(),
+            key: identity.to_key(),
+            kind: identity.kind,
+            label: identity.label,
+            value: identity.value,
+            verified: reason.is_none(),
+            reason: reason.map(|reason| reason.to_string()),
+        };
+        if out_tx.send(message).await.is_err() {
+            return;
+        }
+    }
+
+    let _ = out_tx.send(ServerMessage::Done { did }).await;
+}
@@ -0,0 +1,310 @@
+use anyhow::Result;
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::{header::RETRY_AFTER, HeaderValue, StatusCode};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    errors::{is_transient_error, QueueFullError},
+    http::{
+        client_limit::{limit_client, too_many_requests_message, ClientIp},
+        context::WebContext,
+    },
+    i18n::{Reason, DEFAULT_LOCALE},
+    identity::Identity,
+    worker::{verify_queued, VerifyWork},
+};
+
+/// The body of an XRPC error response.
+#[derive(Serialize, ToSchema)]
+pub(crate) struct XrpcErrorBody {
+    pub(crate) error: &'static str,
+    pub(crate) message: String,
+}
+
+/// An XRPC error response: `{"error": "...", "message": "..."}`.
+pub(crate) struct XrpcError {
+    status: StatusCode,
+    error: &'static str,
+    message: String,
+    /// Seconds until the request can be retried, sent as `Retry-After`.
+    retry_after: Option<u64>,
+}
+
+impl XrpcError {
+    pub(crate) fn invalid_request(message: &str) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error: "InvalidRequest",
+            message: message.to_string(),
+            retry_after: None,
+        }
+    }
+
+    /// A definitive lookup failure is reported as `error`, and a transient one as an upstream
+    /// failure that can be retried.
+    pub(crate) fn lookup_failed(error: &'static str, err: &anyhow::Error) -> Self {
+        if is_transient_error(err) {
+            Self {
+                status: StatusCode::BAD_GATEWAY,
+                error: "UpstreamFailure",
+                message: err.to_string(),
+                retry_after: None,
+            }
+        } else {
+            Self {
+                status: StatusCode::BAD_REQUEST,
+                error,
+                message: err.to_string(),
+                retry_after: None,
+            }
+        }
+    }
+
+    /// Verification is rejected while the verification queue is full.
+    pub(crate) fn verify_failed(err: &anyhow::Error) -> Self {
+        if err.is::<QueueFullError>() {
+            Self {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                error: "ServiceUnavailable",
+                message: err.to_string(),
+                retry_after: None,
+            }
+        } else {
+            Self::internal(err)
+        }
+    }
+
+    /// The client is making requests too quickly.
+    pub(crate) fn rate_limited(retry_after: u64) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            error: "RateLimitExceeded",
+            message: too_many_requests_message(DEFAULT_LOCALE, retry_after),
+            retry_after: Some(retry_after),
+        }
+    }
+
+    pub(crate) fn internal(err: &anyhow::Error) -> Self {
+        tracing::error!(error = ?err, "internal server error");
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: "InternalServerError",
+            message: "Internal Server Error".to_string(),
+            retry_after: None,
+        }
+    }
+}
+
+impl IntoResponse for XrpcError {
+    fn into_response(self) -> Response {
+        let mut response = (
+            self.status,
+            Json(XrpcErrorBody {
+                error: self.error,
+                message: self.message,
+            }),
+        )
+            .into_response();
+        if let Some(retry_after) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub(crate) struct GetVerificationsParams {
+    /// The DID to verify the identities of.
+    did: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct Verification {
+    pub(crate) key: String,
+    pub(crate) kind: String,
+    pub(crate) label: String,
+    pub(crate) value: String,
+    pub(crate) verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<String>,
+}
+
+impl Verification {
+    fn from_outcome((identity, reason): (Identity, Option<Reason>)) -> Self {
+        Self {
+            key: identity.to_key(),
+            kind: identity.kind,
+            label: identity.label,
+            value: identity.value,
+            verified: reason.is_none(),
+            reason: reason.map(|reason| reason.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct GetVerificationsOutput {
+    pub(crate) did: String,
+    pub(crate) verifications: Vec<Verification>,
+}
+
+impl GetVerificationsOutput {
+    pub(crate) fn from_results(did: String, results: Vec<VerifyWork>) -> Self {
+        let verifications = results
+            .into_iter()
+            .filter_map(|work| work.into_outcome())
+            .map(Verification::from_outcome)
+            .collect();
+        Self { did, verifications }
+    }
+}
+
+/// `cool.astrenox.weathervane.getVerifications`, described by
+/// `lexicons/cool/astrenox/weathervane/getVerifications.json`.
+#[utoipa::path(
+    get,
+    path = "/xrpc/cool.astrenox.weathervane.getVerifications",
+    tag = "xrpc",
+    params(GetVerificationsParams),
+    responses(
+        (status = 200, description = "The verification of each identity.", body = GetVerificationsOutput),
+        (status = 400, description = "`InvalidRequest` or `DidNotFound`.", body = XrpcErrorBody),
+        (status = 429, description = "`RateLimitExceeded`.", body = XrpcErrorBody),
+        (status = 502, description = "`UpstreamFailure`.", body = XrpcErrorBody),
+        (status = 503, description = "`ServiceUnavailable`, when the verification queue is full.", body = XrpcErrorBody),
+    )
+)]
+pub(crate) async fn handle_get_verifications(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    Query(params): Query<GetVerificationsParams>,
+) -> Result<impl IntoResponse, XrpcError> {
+    limit_client(web_context.api_limiter.as_ref(), &client_ip)
+        .await
+        .map_err(XrpcError::rate_limited)?;
+
+    let did = params
+        .did
+        .filter(|did| did.starts_with("did:"))
+        .ok_or(XrpcError::invalid_request("did must be a DID"))?;
+
+    let document = web_context
+        .verifier
+        .fetch_did_document(&did)
+        .await
+        .map_err(|err| XrpcError::lookup_failed("DidNotFound", &err))?;
+    let identities = web_context.verifier.parse_identities(&document);
+
+    let results = verify_queued(&web_context.verify_work_tx, &document.id, identities, false)
+        .await
+        .map_err(|err| XrpcError::verify_failed(&err))?;
+
+    Ok(Json(GetVerificationsOutput::from_results(
+        document.id,
+        results,
+    )))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub(crate) struct ResolveSubjectParams {
+    /// A handle, an at:// handle or a DID.
+    subject: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ResolveSubjectOutput {
+    pub(crate) subject: String,
+    pub(crate) did: String,
+}
+
+/// `cool.astrenox.weathervane.resolveSubject`, described by
+/// `lexicons/cool/astrenox/weathervane/resolveSubject.json`.
+#[utoipa::path(
+    get,
+    path = "/xrpc/cool.astrenox.weathervane.resolveSubject",
+    tag = "xrpc",
+    params(ResolveSubjectParams),
+    responses(
+        (status = 200, description = "The DID of the subject.", body = ResolveSubjectOutput),
+        (status = 400, description = "`InvalidRequest` or `SubjectNotFound`.", body = XrpcErrorBody),
+        (status = 429, description = "`RateLimitExceeded`.", body = XrpcErrorBody),
+        (status = 502, description = "`UpstreamFailure`.", body = XrpcErrorBody),
+    )
+)]
+pub(crate) async fn handle_resolve_subject(
+    State(web_context): State<WebContext>,
+    client_ip: ClientIp,
+    Query(params): Query<ResolveSubjectParams>,
+) -> Result<impl IntoResponse, XrpcError> {
+    limit_client(web_context.api_limiter.as_ref(), &client_ip)
+        .await
+        .map_err(XrpcError::rate_limited)?;
+
+    let subject = params
+        .subject
+        .filter(|subject| !subject.is_empty())
+        .ok_or(XrpcError::invalid_request("subject is required"))?;
+
+    let did = web_context
+        .verifier
+        .resolve_subject(&subject)
+        .await
+        .map_err(|err| XrpcError::lookup_failed("SubjectNotFound", &err))?;
+
+    Ok(Json(ResolveSubjectOutput { subject, did }))
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ServiceDidDocument {
+    #[serde(rename = "@context")]
+    pub(crate) context: Vec<String>,
+    pub(crate) id: String,
+    pub(crate) service: Vec<ServiceEndpoint>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ServiceEndpoint {
+    pub(crate) id: String,
+    #[serde(rename = "type")]
+    pub(crate) service_type: String,
+    #[serde(rename = "serviceEndpoint")]
+    pub(crate) service_endpoint: String,
+}
+
+/// The DID document of the service's own `did:web`, derived from `EXTERNAL_BASE`.
+#[utoipa::path(
+    get,
+    path = "/.well-known/did.json",
+    tag = "xrpc",
+    responses((status = 200, description = "The service DID document.", body = ServiceDidDocument))
+)]
+pub(crate) async fn handle_well_known_did(
+    State(web_context): State<WebContext>,
+) -> Result<impl IntoResponse, XrpcError> {
+    let external_base = url::Url::parse(&web_context.external_base)
+        .map_err(|err| XrpcError::internal(&err.into()))?;
+    let host = external_base
+        .host_str()
+        .ok_or_else(|| XrpcError::internal(&anyhow::anyhow!("EXTERNAL_BASE has no host")))?;
+    let did = match external_base.port() {
+        Some(port) => format!("did:web:{}%3A{}", host, port),
+        None => format!("did:web:{}", host),
+    };
+
+    Ok(Json(ServiceDidDocument {
+        context: vec!["https://www.w3.org/ns/did/v1".to_string()],
+        id: did,
+        service: vec![ServiceEndpoint {
+            id: "#weathervane".to_string(),
+            service_type: "WeatherVane".to_string(),
+            service_endpoint: web_context.external_base.trim_end_matches('/').to_string(),
+        }],
+    }))
+}
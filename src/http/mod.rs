@@ -1,9 +1,20 @@
+pub mod client_limit;
 pub mod context;
+pub mod handle_admin;
+pub mod handle_badge;
 pub mod handle_did;
+pub mod handle_did_recheck;
 pub mod handle_did_stream;
 pub mod handle_guide;
+pub mod handle_health;
 pub mod handle_index;
+pub mod handle_metrics;
 pub mod handle_validate;
+pub mod handle_verify_batch;
+pub mod handle_ws;
+pub mod handle_xrpc;
+pub mod negotiate;
+pub mod openapi;
 pub mod server;
 pub mod templates;
 pub mod view_identity;
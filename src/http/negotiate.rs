@@ -0,0 +1,111 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    middleware::Next,
+    response::Response,
+};
+use http::{
+    header::{ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, SET_COOKIE, VARY},
+    request::Parts,
+    HeaderValue,
+};
+use std::convert::Infallible;
+
+use crate::i18n::{supported_locale, DEFAULT_LOCALE};
+
+/// The query parameter and cookie that select a locale.
+const LOCALE_PARAMETER: &str = "lang";
+
+/// Parses a header such as `Accept` or `Accept-Language` into its values, highest quality first.
+/// Values with the same quality keep their order, and values with a quality of zero are dropped.
+pub(crate) fn quality_values(header: &str) -> Vec<(String, f32)> {
+    let mut values: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let value = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!value.is_empty() && quality > 0.0).then_some((value, quality))
+        })
+        .collect();
+    values.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    values
+}
+
+/// The locale of the web UI, chosen by the `lang` query parameter, then the `lang` cookie, then
+/// `Accept-Language`.
+pub(crate) struct Locale(pub(crate) &'static str);
+
+impl Locale {
+    fn from_query(parts: &Parts) -> Option<&'static str> {
+        let query = parts.uri.query()?;
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == LOCALE_PARAMETER)
+            .and_then(|(_, value)| supported_locale(&value))
+    }
+
+    fn from_cookie(parts: &Parts) -> Option<&'static str> {
+        parts
+            .headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == LOCALE_PARAMETER)
+            .and_then(|(_, value)| supported_locale(value))
+    }
+
+    fn from_accept_language(parts: &Parts) -> Option<&'static str> {
+        let accept_language = parts.headers.get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+        quality_values(accept_language)
+            .iter()
+            .find_map(|(tag, _)| supported_locale(tag))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Locale {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let locale = Self::from_query(parts)
+            .or_else(|| Self::from_cookie(parts))
+            .or_else(|| Self::from_accept_language(parts))
+            .unwrap_or(DEFAULT_LOCALE);
+        Ok(Locale(locale))
+    }
+}
+
+/// Remembers a locale chosen with the `lang` query parameter in a cookie, so that later pages
+/// and the DID page update stream use it too.
+pub(crate) async fn remember_locale(request: Request, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    let chosen = Locale::from_query(&parts);
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if is_html {
+        response
+            .headers_mut()
+            .append(VARY, HeaderValue::from_static("accept-language, cookie"));
+    }
+
+    if let Some(locale) = chosen {
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age=31536000; SameSite=Lax",
+            LOCALE_PARAMETER, locale
+        );
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(SET_COOKIE, cookie);
+        }
+    }
+    response
+}
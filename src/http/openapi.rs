@@ -0,0 +1,108 @@
+use axum::{response::IntoResponse, Json};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    health::{CheckResult, Readiness},
+    http::{
+        handle_admin::{self, AdminCaches},
+        handle_badge, handle_did, handle_did_recheck,
+        handle_did_stream::{self, IdentityEventView, ProgressView, SummaryView},
+        handle_guide,
+        handle_health::{self, HealthStatus},
+        handle_index, handle_metrics, handle_validate,
+        handle_verify_batch::{self, BatchRequest, BatchResult},
+        handle_ws::{self, ClientMessage, ServerMessage},
+        handle_xrpc::{
+            self, GetVerificationsOutput, ResolveSubjectOutput, ServiceDidDocument,
+            ServiceEndpoint, Verification, XrpcErrorBody,
+        },
+    },
+    identity::Identity,
+    verifier::{CacheSizes, CachedVerification, DidCacheEntries},
+};
+
+/// The OpenAPI document of every route, served at `/api/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "WeatherVane", description = "Verifies the identities linked to ATProto DIDs."),
+    paths(
+        handle_index::handle_index,
+        handle_guide::handle_guide,
+        handle_validate::handle_validate,
+        handle_did::handle_did,
+        handle_did_stream::handle_did_stream,
+        handle_did_recheck::handle_did_recheck,
+        handle_verify_batch::handle_verify_batch,
+        handle_ws::handle_ws,
+        handle_xrpc::handle_get_verifications,
+        handle_xrpc::handle_resolve_subject,
+        handle_xrpc::handle_well_known_did,
+        handle_badge::handle_badge,
+        handle_badge::handle_badge_identity,
+        handle_health::handle_healthz,
+        handle_health::handle_readyz,
+        handle_metrics::handle_metrics,
+        handle_admin::handle_admin_caches,
+        handle_admin::handle_admin_cache_did,
+        handle_admin::handle_admin_cache_purge,
+        handle_admin::handle_admin_cache_refresh,
+        handle_openapi,
+    ),
+    components(schemas(
+        AdminCaches,
+        BatchRequest,
+        BatchResult,
+        CacheSizes,
+        CachedVerification,
+        CheckResult,
+        ClientMessage,
+        DidCacheEntries,
+        GetVerificationsOutput,
+        HealthStatus,
+        Identity,
+        IdentityEventView,
+        ProgressView,
+        Readiness,
+        ResolveSubjectOutput,
+        ServerMessage,
+        ServiceDidDocument,
+        ServiceEndpoint,
+        SummaryView,
+        Verification,
+        XrpcErrorBody,
+    )),
+    modifiers(&DocumentDetails),
+)]
+pub struct ApiDoc;
+
+/// Adds the `ADMIN_TOKEN` bearer scheme used by the admin routes, and drops the empty license
+/// taken from the package metadata.
+struct DocumentDetails;
+
+impl Modify for DocumentDetails {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.info.license = None;
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Serves the OpenAPI document.
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "api",
+    responses((status = 200, description = "The OpenAPI document.", content_type = "application/json"))
+)]
+pub(crate) async fn handle_openapi() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,482 @@
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cache::{CacheConfig, VerifyResult},
+    config::Config,
+    did::{DidDocument, DidMethodResolver},
+    health::ReadinessCheck,
+    http::{
+        context::{AppEngine, WebContext},
+        server::build_router,
+    },
+    i18n::Reason,
+    identity::IdentityValidator,
+    rate_limit::RateLimits,
+    test_server::serve,
+    verifier::{Verifier, VerifierOptions},
+    worker::{QueueWork, VerifyTask},
+};
+
+const DID: &str = "did:plc:cbkjy5n7bk3ax2wplmtjofq2";
+const ADMIN_TOKEN: &str = "admin-token";
+
+/// Resolves every `did:plc` DID to a document listing a verified and an unverified identity.
+struct TestResolver;
+
+#[async_trait]
+impl DidMethodResolver for TestResolver {
+    fn method(&self) -> &str {
+        "plc"
+    }
+
+    async fn resolve(&self, did: &str) -> anyhow::Result<DidDocument> {
+        DidDocument::from_value(json!({
+            "id": did,
+            "alsoKnownAs": ["test:linked", "test:unlinked"],
+        }))
+    }
+}
+
+/// Verifies `test:` identities without contacting anything.
+struct TestValidator;
+
+#[async_trait]
+impl IdentityValidator for TestValidator {
+    fn kind(&self) -> &str {
+        "test"
+    }
+
+    fn label(&self) -> &str {
+        "Test"
+    }
+
+    fn parse(&self, value: &str) -> Option<String> {
+        value.strip_prefix("test:").map(str::to_string)
+    }
+
+    async fn verify(&self, _did: &str, identity_value: &str) -> VerifyResult {
+        match identity_value {
+            "linked" => VerifyResult::Found,
+            _ => VerifyResult::NotFound(Reason::text("not linked")),
+        }
+    }
+
+    fn guide(&self) -> &str {
+        ""
+    }
+}
+
+/// Serves the application with its real handlers and returns its base URL.
+async fn serve_app() -> String {
+    let port = 4060;
+    let external_base = format!("http://localhost:{}", port);
+    let config = Config {
+        version: "test".to_string(),
+        http_port: port.to_string().try_into().unwrap(),
+        external_base: external_base.clone(),
+        certificate_bundles: String::new().try_into().unwrap(),
+        user_agent: "weathervane-test".to_string(),
+        // Nothing listens there, so the readiness check fails quickly.
+        plc_hostname: "127.0.0.1:9".to_string(),
+        verify_concurrency: 2,
+        verify_queue_size: 10,
+        watched_dids: String::new().try_into().unwrap(),
+        watched_dids_source: None,
+        watch_interval: "15m".to_string().try_into().unwrap(),
+        webhook_urls: String::new().try_into().unwrap(),
+        webhook_secret: None,
+        jetstream_url: String::new().try_into().unwrap(),
+        jetstream_replay: None,
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        cache: CacheConfig::default(),
+        rate_limits: RateLimits::default(),
+    };
+
+    let http_client = reqwest::Client::new();
+    let verifier = Verifier::new(
+        &http_client,
+        VerifierOptions {
+            plc_hostname: config.plc_hostname.clone(),
+            ..VerifierOptions::default()
+        },
+    )
+    .with_resolver(Arc::new(TestResolver))
+    .with_validator(Arc::new(TestValidator));
+
+    let (verify_work_tx, mut verify_work_rx) =
+        tokio::sync::mpsc::channel::<QueueWork>(config.verify_queue_size);
+    let verify_task = VerifyTask::new(
+        verifier.clone(),
+        config.verify_concurrency,
+        CancellationToken::new(),
+    );
+    let readiness_check = ReadinessCheck::new(
+        &http_client,
+        &config.plc_hostname,
+        verify_work_tx.clone(),
+        verify_task.status(),
+    );
+    let queue_progress = verify_task.progress();
+    tokio::spawn(async move { verify_task.run_background(&mut verify_work_rx).await });
+
+    #[cfg(feature = "embed")]
+    let jinja = crate::http::templates::embed_env::build_env(
+        config.external_base.clone(),
+        config.version.clone(),
+    );
+    #[cfg(feature = "reload")]
+    let jinja =
+        crate::http::templates::reload_env::build_env(&config.external_base, &config.version);
+
+    let web_context = WebContext::new(
+        &config,
+        AppEngine::from(jinja),
+        verify_work_tx,
+        verifier,
+        metrics_exporter_prometheus::PrometheusBuilder::new()
+            .build_recorder()
+            .handle(),
+        readiness_check,
+        queue_progress,
+    );
+    serve(build_router(web_context)).await
+}
+
+/// The API of the application under test, described by the document it serves.
+struct Api {
+    base: String,
+    client: reqwest::Client,
+    document: Value,
+}
+
+impl Api {
+    async fn new() -> Self {
+        let base = serve_app().await;
+        let client = reqwest::Client::new();
+        let document = client
+            .get(format!("{}/api/openapi.json", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        Self {
+            base,
+            client,
+            document,
+        }
+    }
+
+    /// Validates a value against a schema of the served document.
+    ///
+    /// Object schemas are closed first, so a property that the handlers send but the document
+    /// doesn't describe is reported along with missing and mistyped ones.
+    fn assert_matches(&self, schema: &Value, value: &Value, context: &str) {
+        let mut components = self.document["components"].clone();
+        close_objects(&mut components);
+        let mut schema = schema.clone();
+        close_objects(&mut schema);
+        let validator = jsonschema::draft202012::new(&json!({
+            "allOf": [schema],
+            "components": components,
+        }))
+        .unwrap_or_else(|err| panic!("{}: invalid schema: {}", context, err));
+
+        let errors: Vec<String> = validator
+            .iter_errors(value)
+            .map(|err| format!("{} at {}", err, err.instance_path))
+            .collect();
+        assert!(
+            errors.is_empty(),
+            "{} drifted from its schema: {:?}\n{}",
+            context,
+            errors,
+            value
+        );
+    }
+
+    fn assert_component(&self, name: &str, value: &Value) {
+        assert!(
+            self.document["components"]["schemas"].get(name).is_some(),
+            "{} is not a schema component",
+            name
+        );
+        self.assert_matches(
+            &json!({ "$ref": format!("#/components/schemas/{}", name) }),
+            value,
+            name,
+        );
+    }
+
+    /// Checks a response against the schema documented for its path, method and status.
+    async fn assert_response(&self, path: &str, method: &str, response: reqwest::Response) {
+        let status = response.status().as_u16().to_string();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or_default()
+            .to_string();
+        let body = response.text().await.unwrap();
+        let context = format!(
+            "{} {} {} {}",
+            method.to_uppercase(),
+            path,
+            status,
+            content_type
+        );
+
+        let schema = self.document["paths"][path][method]["responses"][&status]["content"]
+            [&content_type]["schema"]
+            .clone();
+        assert!(!schema.is_null(), "{} is not documented", context);
+
+        // Newline-delimited JSON has a schema for each line.
+        let values: Vec<Value> = if content_type == "application/x-ndjson" {
+            body.lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        } else {
+            vec![serde_json::from_str(&body).unwrap()]
+        };
+        assert!(!values.is_empty(), "{} has no body", context);
+        for value in &values {
+            self.assert_matches(&schema, value, &context);
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+}
+
+/// Adds `additionalProperties: false` to the object schemas that list their properties.
+fn close_objects(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            object.values_mut().for_each(close_objects);
+        }
+        Value::Array(values) => values.iter_mut().for_each(close_objects),
+        _ => {}
+    }
+}
+
+/// Splits a server-sent event stream into the name and data of each event.
+fn events(body: &str) -> Vec<(String, String)> {
+    body.split("\n\n")
+        .filter_map(|block| {
+            let mut name = None;
+            let mut data = Vec::new();
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    name = Some(value.trim().to_string());
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            name.map(|name| (name, data.join("\n")))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn health_responses_match_schemas() {
+    let api = Api::new().await;
+
+    for path in ["/healthz", "/readyz"] {
+        let response = api.client.get(api.url(path)).send().await.unwrap();
+        api.assert_response(path, "get", response).await;
+    }
+}
+
+#[tokio::test]
+async fn xrpc_responses_match_schemas() {
+    let api = Api::new().await;
+
+    let path = "/xrpc/cool.astrenox.weathervane.getVerifications";
+    for did in [DID, "not-a-did"] {
+        let response = api
+            .client
+            .get(api.url(path))
+            .query(&[("did", did)])
+            .send()
+            .await
+            .unwrap();
+        api.assert_response(path, "get", response).await;
+    }
+
+    let path = "/xrpc/cool.astrenox.weathervane.resolveSubject";
+    for subject in [DID, ""] {
+        let response = api
+            .client
+            .get(api.url(path))
+            .query(&[("subject", subject)])
+            .send()
+            .await
+            .unwrap();
+        api.assert_response(path, "get", response).await;
+    }
+
+    let path = "/.well-known/did.json";
+    let response = api.client.get(api.url(path)).send().await.unwrap();
+    api.assert_response(path, "get", response).await;
+
+    let response = api
+        .client
+        .get(api.url(&format!("/did/{}", DID)))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .unwrap();
+    api.assert_response("/did/{did}", "get", response).await;
+}
+
+#[tokio::test]
+async fn batch_responses_match_schemas() {
+    let api = Api::new().await;
+
+    let request = json!({ "subjects": [DID, "did:plc:missing"] });
+    api.assert_component("BatchRequest", &request);
+    let response = api
+        .client
+        .post(api.url("/api/verify/batch"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    api.assert_response("/api/verify/batch", "post", response)
+        .await;
+
+    let response = api
+        .client
+        .post(api.url("/api/verify/batch"))
+        .json(&json!([]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    api.assert_response("/api/verify/batch", "post", response)
+        .await;
+}
+
+#[tokio::test]
+async fn admin_responses_match_schemas() {
+    let api = Api::new().await;
+
+    // Caches an entry of each kind first.
+    api.client
+        .get(api.url(&format!("/did/{}", DID)))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .unwrap();
+
+    let did_path = format!("/admin/caches/{}", DID);
+    let requests = [
+        (
+            "/admin/caches",
+            "get",
+            api.client.get(api.url("/admin/caches")),
+        ),
+        (
+            "/admin/caches/{did}",
+            "get",
+            api.client.get(api.url(&did_path)),
+        ),
+        (
+            "/admin/caches/{did}/refresh",
+            "post",
+            api.client.post(api.url(&format!("{}/refresh", did_path))),
+        ),
+        (
+            "/admin/caches/{did}",
+            "delete",
+            api.client.delete(api.url(&did_path)),
+        ),
+    ];
+    for (path, method, request) in requests {
+        let response = request.bearer_auth(ADMIN_TOKEN).send().await.unwrap();
+        api.assert_response(path, method, response).await;
+    }
+}
+
+#[tokio::test]
+async fn stream_events_match_schemas() {
+    let api = Api::new().await;
+
+    let body = api
+        .client
+        .get(api.url(&format!("/did/{}/updates", DID)))
+        .query(&[("format", "json")])
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    let events = events(&body);
+    for (name, component) in [
+        ("identity", "IdentityEventView"),
+        ("progress", "ProgressView"),
+        ("summary", "SummaryView"),
+    ] {
+        let data: Vec<&String> = events
+            .iter()
+            .filter(|(event, _)| event == name)
+            .map(|(_, data)| data)
+            .collect();
+        assert!(!data.is_empty(), "no {} events in {}", name, body);
+        for data in data {
+            api.assert_component(component, &serde_json::from_str(data).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn websocket_messages_match_schemas() {
+    let api = Api::new().await;
+
+    let url = api.url("/api/ws").replacen("http", "ws", 1);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+    let subscribe = json!({ "type": "subscribe", "did": DID });
+    api.assert_component("ClientMessage", &subscribe);
+    socket
+        .send(Message::Text("not a message".to_string()))
+        .await
+        .unwrap();
+    socket
+        .send(Message::Text(subscribe.to_string()))
+        .await
+        .unwrap();
+
+    let mut types = Vec::new();
+    while !types.contains(&"done".to_string()) {
+        let text = match socket.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            message => panic!("unexpected message {:?}", message),
+        };
+        let message: Value = serde_json::from_str(&text).unwrap();
+        api.assert_component("ServerMessage", &message);
+        types.push(message["type"].as_str().unwrap_or_default().to_string());
+    }
+    for expected in ["error", "subscribed", "identity", "done"] {
+        assert!(
+            types.iter().any(|message_type| message_type == expected),
+            "no {} message in {:?}",
+            expected,
+            types
+        );
+    }
+}
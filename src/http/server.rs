@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use axum::{
     http::HeaderValue,
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -12,15 +13,36 @@ use http::{
 };
 use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
-use tower_http::{cors::CorsLayer, services::ServeDir};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    services::ServeDir,
+};
 
 use crate::http::{
-    context::WebContext, handle_did::handle_did, handle_did_stream::handle_did_stream,
-    handle_guide::handle_guide, handle_index::handle_index, handle_validate::handle_validate,
+    context::WebContext,
+    handle_admin::{
+        handle_admin_cache_did, handle_admin_cache_purge, handle_admin_cache_refresh,
+        handle_admin_caches,
+    },
+    handle_badge::{handle_badge, handle_badge_identity},
+    handle_did::handle_did,
+    handle_did_recheck::handle_did_recheck,
+    handle_did_stream::handle_did_stream,
+    handle_guide::handle_guide,
+    handle_health::{handle_healthz, handle_readyz},
+    handle_index::handle_index,
+    handle_metrics::handle_metrics,
+    handle_validate::handle_validate,
+    handle_verify_batch::handle_verify_batch,
+    handle_ws::handle_ws,
+    handle_xrpc::{handle_get_verifications, handle_resolve_subject, handle_well_known_did},
+    negotiate::remember_locale,
+    openapi::handle_openapi,
 };
 
 pub fn build_router(web_context: WebContext) -> Router {
     let serve_dir = ServeDir::new("static");
+    let external_base = web_context.external_base.parse::<HeaderValue>().unwrap();
 
     Router::new()
         .route("/", get(handle_index))
@@ -28,15 +50,46 @@ pub fn build_router(web_context: WebContext) -> Router {
         .route("/validate", post(handle_validate))
         .route("/did/:did", get(handle_did))
         .route("/did/:did/updates", get(handle_did_stream))
+        .route("/did/:did/recheck", post(handle_did_recheck))
+        .route("/api/verify/batch", post(handle_verify_batch))
+        .route("/api/ws", get(handle_ws))
+        .route("/api/openapi.json", get(handle_openapi))
+        .route(
+            "/xrpc/cool.astrenox.weathervane.getVerifications",
+            get(handle_get_verifications),
+        )
+        .route(
+            "/xrpc/cool.astrenox.weathervane.resolveSubject",
+            get(handle_resolve_subject),
+        )
+        .route("/.well-known/did.json", get(handle_well_known_did))
+        .route("/badge/:did", get(handle_badge))
+        .route("/badge/:did/:identity", get(handle_badge_identity))
+        .route("/healthz", get(handle_healthz))
+        .route("/readyz", get(handle_readyz))
+        .route("/metrics", get(handle_metrics))
+        .route("/admin/caches", get(handle_admin_caches))
+        .route(
+            "/admin/caches/:did",
+            get(handle_admin_cache_did).delete(handle_admin_cache_purge),
+        )
+        .route(
+            "/admin/caches/:did/refresh",
+            post(handle_admin_cache_refresh),
+        )
         .nest_service("/static", serve_dir.clone())
         .fallback_service(serve_dir)
+        .layer(middleware::from_fn(remember_locale))
         .layer((
             TraceLayer::new_for_http(),
             TimeoutLayer::new(Duration::from_secs(10)),
         ))
         .layer(
             CorsLayer::new()
-                .allow_origin(web_context.external_base.parse::<HeaderValue>().unwrap())
+                .allow_origin(AllowOrigin::predicate(move |origin, parts| {
+                    // XRPC methods can be called from any origin, such as ATProto web clients.
+                    origin == external_base || parts.uri.path().starts_with("/xrpc/")
+                }))
                 .allow_methods([Method::GET])
                 .allow_headers([ACCEPT_LANGUAGE, ACCEPT]),
         )
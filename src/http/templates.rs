@@ -1,3 +1,41 @@
+use fluent_bundle::FluentArgs;
+use minijinja::{
+    value::{Kwargs, Value},
+    Environment, Error, State,
+};
+
+use crate::i18n::{locales, message, DEFAULT_LOCALE};
+
+/// Adds the `t` function and the `locales` global, a list of `(locale, name)` pairs for the
+/// language picker.
+fn add_i18n(env: &mut Environment) {
+    env.add_function("t", translate);
+    let locales: Vec<(&str, String)> = locales()
+        .map(|locale| (locale, message(locale, "locale-name", None)))
+        .collect();
+    env.add_global("locales", Value::from_serialize(locales));
+}
+
+/// `t("message-id", name=value, ...)` formats a message in the `locale` of the template context.
+fn translate(state: &State, id: &str, kwargs: Kwargs) -> Result<String, Error> {
+    let locale = state.lookup("locale");
+    let locale = locale
+        .as_ref()
+        .and_then(|locale| locale.as_str())
+        .unwrap_or(DEFAULT_LOCALE);
+
+    let mut args = FluentArgs::new();
+    for name in kwargs.args() {
+        let value: Value = kwargs.get(name)?;
+        // Numbers stay numbers so that messages can select plural forms.
+        match i64::try_from(value.clone()) {
+            Ok(number) => args.set(name.to_string(), number),
+            Err(_) => args.set(name.to_string(), value.to_string()),
+        }
+    }
+    Ok(message(locale, id, Some(&args)))
+}
+
 #[cfg(feature = "reload")]
 pub mod reload_env {
     use std::path::PathBuf;
@@ -16,6 +54,7 @@ pub mod reload_env {
             env.add_global("base", http_external.clone());
             env.add_global("version", version.clone());
             env.set_loader(path_loader(&template_path));
+            super::add_i18n(&mut env);
             notifier.set_fast_reload(true);
             notifier.watch_path(&template_path, true);
             Ok(env)
@@ -34,6 +73,7 @@ pub mod embed_env {
         env.add_global("base", http_external.clone());
         env.add_global("version", version.clone());
         minijinja_embed::load_templates!(&mut env);
+        super::add_i18n(&mut env);
         env
     }
 }
@@ -0,0 +1,120 @@
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use std::{collections::HashMap, fmt, sync::LazyLock};
+use unic_langid::LanguageIdentifier;
+
+/// The locale used when no other locale is requested, and for messages missing from a catalog.
+pub const DEFAULT_LOCALE: &str = "en-us";
+
+/// The message catalog of each supported locale, in the order they are offered to visitors.
+const CATALOGS: [(&str, &str); 2] = [
+    ("en-us", include_str!("../locales/en-us.ftl")),
+    ("fr-fr", include_str!("../locales/fr-fr.ftl")),
+];
+
+static BUNDLES: LazyLock<HashMap<&'static str, FluentBundle<FluentResource>>> =
+    LazyLock::new(|| {
+        CATALOGS
+            .iter()
+            .map(|(locale, catalog)| {
+                let language: LanguageIdentifier = locale
+                    .parse()
+                    .unwrap_or_else(|err| panic!("invalid locale {}: {}", locale, err));
+                let resource = FluentResource::try_new(catalog.to_string())
+                    .unwrap_or_else(|(_, errs)| panic!("invalid {} catalog: {:?}", locale, errs));
+                let mut bundle = FluentBundle::new_concurrent(vec![language]);
+                // Messages are rendered into HTML and plain text, where the Unicode isolation
+                // marks around arguments would show up as stray characters.
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .unwrap_or_else(|errs| panic!("invalid {} catalog: {:?}", locale, errs));
+                (*locale, bundle)
+            })
+            .collect()
+    });
+
+/// The supported locales.
+pub fn locales() -> impl Iterator<Item = &'static str> {
+    CATALOGS.iter().map(|(locale, _)| *locale)
+}
+
+/// Returns the supported locale for a language tag such as `fr-FR`, or for a bare language such
+/// as `fr`.
+pub fn supported_locale(tag: &str) -> Option<&'static str> {
+    let tag = tag.trim().to_ascii_lowercase().replace('_', "-");
+    if tag.is_empty() {
+        return None;
+    }
+    locales().find(|locale| *locale == tag).or_else(|| {
+        let language = tag.split('-').next().unwrap_or_default();
+        locales().find(|locale| locale.split('-').next() == Some(language))
+    })
+}
+
+/// Formats a message of the catalog of a locale, if the catalog has it.
+pub fn try_message(locale: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let bundle = BUNDLES.get(locale)?;
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let message = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        tracing::warn!(locale, id, ?errors, "formatting message failed");
+    }
+    Some(message.into_owned())
+}
+
+/// Formats a message, falling back to the default locale and then to the message id.
+pub fn message(locale: &str, id: &str, args: Option<&FluentArgs>) -> String {
+    try_message(locale, id, args)
+        .or_else(|| try_message(DEFAULT_LOCALE, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Why an identity was not verified, as a message of the catalogs in `locales/` and its
+/// arguments. It displays in the default locale.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reason {
+    pub id: &'static str,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Reason {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            args: Vec::new(),
+        }
+    }
+
+    /// A reason that isn't in the catalogs, such as one from a custom validator.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new("reason-message").arg("text", text)
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+
+    /// The value of an argument.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn localize(&self, locale: &str) -> String {
+        let mut args = FluentArgs::new();
+        for (name, value) in &self.args {
+            args.set(*name, value.as_str());
+        }
+        message(locale, self.id, Some(&args))
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(DEFAULT_LOCALE))
+    }
+}
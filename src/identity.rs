@@ -1,73 +1,155 @@
+use async_trait::async_trait;
 use ordermap::OrderSet;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
 
-use crate::did::is_valid_hostname;
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum IdentityType {
-    DIDMethodPLC(String),
-    DIDMethodWeb(String),
-    Handle(String),
-    Domain(String),
-    GitHub(String),
-    Website(String),
-    Unsupported(String),
+use crate::{
+    cache::VerifyResult,
+    i18n::{try_message, Reason},
+    worker::{did_method_plc, did_method_web, domain, github, handle, website},
+};
+
+/// An `alsoKnownAs` value along with the validator that is responsible for it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, ToSchema)]
+pub struct Identity {
+    pub kind: String,
+    pub label: String,
+    pub value: String,
 }
 
-impl IdentityType {
-    pub(crate) fn pending_string(&self) -> String {
-        match self {
-            IdentityType::DIDMethodPLC(value) => format!("{} [DID-PLC]", value),
-            IdentityType::DIDMethodWeb(value) => format!("{} [DID-WEB]", value),
-            IdentityType::Handle(value) => format!("{} [Handle]", value),
-            IdentityType::Domain(value) => format!("{} [DNS]", value),
-            IdentityType::GitHub(value) => format!("{} [GitHub]", value),
-            IdentityType::Website(value) => format!("{} [WWW]", value),
-            IdentityType::Unsupported(value) => format!("{} [Unknown]", value),
+impl Identity {
+    pub fn unsupported(value: &str) -> Self {
+        Self {
+            kind: "unsupported".to_string(),
+            label: "Unknown".to_string(),
+            value: value.to_string(),
         }
     }
 
-    pub(crate) fn to_key(&self) -> String {
-        match self {
-            IdentityType::DIDMethodPLC(value)
-            | IdentityType::DIDMethodWeb(value)
-            | IdentityType::Handle(value)
-            | IdentityType::Domain(value)
-            | IdentityType::GitHub(value)
-            | IdentityType::Website(value)
-            | IdentityType::Unsupported(value) => cityhasher::hash::<u64>(value).to_string(),
-        }
+    pub fn pending_string(&self) -> String {
+        format!("{} [{}]", self.value, self.label)
+    }
+
+    /// The value along with the label of its kind in a locale.
+    pub fn localized_pending_string(&self, locale: &str) -> String {
+        format!("{} [{}]", self.value, self.localized_label(locale))
+    }
+
+    /// The label of the identity kind in a locale, or the validator's label for kinds without a
+    /// translation.
+    pub fn localized_label(&self, locale: &str) -> String {
+        try_message(locale, &format!("label-{}", self.kind), None)
+            .unwrap_or_else(|| self.label.clone())
+    }
+
+    pub fn to_key(&self) -> String {
+        cityhasher::hash::<u64>(&self.value).to_string()
     }
 }
 
-pub(crate) fn parse_identities(values: &[String]) -> OrderSet<IdentityType> {
-    values.iter().map(|value| parse_identity(value)).collect()
+/// Parses and verifies one kind of identity.
+#[async_trait]
+pub trait IdentityValidator: Send + Sync {
+    /// A unique name for the identity kind, e.g. `handle`.
+    fn kind(&self) -> &str;
+
+    /// The short label displayed next to identities, e.g. `Handle`.
+    fn label(&self) -> &str;
+
+    /// Returns the value to verify if the `alsoKnownAs` value is handled by this validator.
+    fn parse(&self, value: &str) -> Option<String>;
+
+    /// Verifies that the identity references the DID.
+    async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult;
+
+    /// A short description of how to link this kind of identity to a DID.
+    fn guide(&self) -> &str;
+
+    /// The host contacted to verify the identity, which verifications are rate limited by.
+    /// Identities verified through a shared service, such as the PLC directory, have none.
+    fn target_host(&self, _identity_value: &str) -> Option<String> {
+        None
+    }
 }
 
-pub(crate) fn parse_identity(value: &str) -> IdentityType {
-    if value.starts_with("did:plc:") {
-        IdentityType::DIDMethodPLC(value.to_string())
-    } else if value.starts_with("did:web:") {
-        IdentityType::DIDMethodWeb(value.to_string())
-    } else if value.starts_with("at://") {
-        IdentityType::Handle(value.to_string())
-    } else if value.strip_prefix("dns:").is_some_and(is_valid_hostname) {
-        IdentityType::Domain(value.to_string())
-    } else if value.starts_with("https://github.com/") {
-        let first = value.strip_prefix("https://github.com/").map(|trimmed| {
-            if let Some((first, _)) = trimmed.split_once("/") {
-                first
-            } else {
-                trimmed
-            }
-        });
-        if let Some(inner_value) = first {
-            IdentityType::GitHub(inner_value.to_string())
-        } else {
-            IdentityType::Unsupported(value.to_string())
+/// The ordered set of identity validators. The first validator that parses a value handles it.
+#[derive(Clone)]
+pub struct IdentityValidators(Arc<Vec<Arc<dyn IdentityValidator>>>);
+
+impl IdentityValidators {
+    pub fn new(validators: Vec<Arc<dyn IdentityValidator>>) -> Self {
+        Self(Arc::new(validators))
+    }
+
+    /// Returns the built-in validators for DIDs, handles, domains, GitHub and websites.
+    pub fn default_validators(http_client: &reqwest::Client, plc_hostname: &str) -> Self {
+        Self::new(vec![
+            Arc::new(did_method_plc::DidMethodPlcValidator::new(
+                http_client,
+                plc_hostname,
+            )),
+            Arc::new(did_method_web::DidMethodWebValidator::new(http_client)),
+            Arc::new(handle::HandleValidator::new(http_client)),
+            Arc::new(domain::DomainValidator),
+            Arc::new(github::GitHubValidator::new(http_client)),
+            Arc::new(website::WebsiteValidator::new(http_client)),
+        ])
+    }
+
+    /// Registers a validator ahead of the existing ones, replacing any validator of the same kind.
+    pub fn with_validator(self, validator: Arc<dyn IdentityValidator>) -> Self {
+        let mut validators = vec![validator.clone()];
+        validators.extend(
+            self.0
+                .iter()
+                .filter(|existing| existing.kind() != validator.kind())
+                .cloned(),
+        );
+        Self::new(validators)
+    }
+
+    pub fn validators(&self) -> impl Iterator<Item = &Arc<dyn IdentityValidator>> {
+        self.0.iter()
+    }
+
+    pub fn parse_identities(&self, values: &[String]) -> OrderSet<Identity> {
+        values
+            .iter()
+            .map(|value| self.parse_identity(value))
+            .collect()
+    }
+
+    pub fn parse_identity(&self, value: &str) -> Identity {
+        self.0
+            .iter()
+            .find_map(|validator| {
+                validator.parse(value).map(|identity_value| Identity {
+                    kind: validator.kind().to_string(),
+                    label: validator.label().to_string(),
+                    value: identity_value,
+                })
+            })
+            .unwrap_or_else(|| Identity::unsupported(value))
+    }
+
+    /// The lowercase host contacted to verify the identity, if any.
+    pub fn target_host(&self, identity: &Identity) -> Option<String> {
+        self.0
+            .iter()
+            .find(|validator| validator.kind() == identity.kind)?
+            .target_host(&identity.value)
+            .map(|host| host.to_lowercase())
+    }
+
+    pub async fn verify(&self, did: &str, identity: &Identity) -> VerifyResult {
+        match self
+            .0
+            .iter()
+            .find(|validator| validator.kind() == identity.kind)
+        {
+            Some(validator) => validator.verify(did, &identity.value).await,
+            None => VerifyResult::NotFound(Reason::new("reason-unsupported-identity")),
         }
-    } else if value.starts_with("https://") || value.starts_with("http://") {
-        IdentityType::Website(value.to_string())
-    } else {
-        IdentityType::Unsupported(value.to_string())
     }
 }
@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::verifier::Verifier;
+
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How far back, in microseconds, to resume from after reconnecting so no events are missed.
+const CURSOR_REWIND: u64 = 5_000_000;
+
+/// Where identity and account events are read from.
+#[derive(Clone, Debug)]
+pub enum JetstreamSource {
+    /// A Jetstream `subscribe` endpoint, or a stand-in server sending the same messages.
+    WebSocket(String),
+    /// A file with one Jetstream message per line, read once.
+    Replay(String),
+}
+
+#[derive(Deserialize)]
+struct JetstreamEvent {
+    did: String,
+    time_us: Option<u64>,
+    kind: String,
+    identity: Option<IdentityEvent>,
+}
+
+#[derive(Deserialize)]
+struct IdentityEvent {
+    handle: Option<String>,
+}
+
+/// Consumes Jetstream `identity` and `account` events, removing the cached DID documents,
+/// handle resolutions and verification results of the DIDs they are about.
+///
+/// Commit events are ignored. Only subscribing to an unused collection, for example
+/// `wantedCollections=cool.astrenox.weathervane.none`, keeps them off the connection.
+pub struct JetstreamTask {
+    verifier: Verifier,
+    source: JetstreamSource,
+    cancellation_token: CancellationToken,
+}
+
+impl JetstreamTask {
+    pub fn new(
+        verifier: Verifier,
+        source: JetstreamSource,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            verifier,
+            source,
+            cancellation_token,
+        }
+    }
+
+    pub async fn run_background(&self) -> Result<()> {
+        tracing::debug!("JetstreamTask started");
+
+        match &self.source {
+            JetstreamSource::Replay(path) => self.replay(path).await?,
+            JetstreamSource::WebSocket(url) => self.subscribe(url).await,
+        }
+
+        tracing::debug!("JetstreamTask stopped");
+
+        Ok(())
+    }
+
+    async fn replay(&self, path: &str) -> Result<()> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let mut cursor = None;
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            if self.cancellation_token.is_cancelled() {
+                break;
+            }
+            if let Err(err) = self.handle_message(line, &mut cursor).await {
+                tracing::warn!(error = ?err, "invalid jetstream message");
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads events until cancelled, reconnecting with backoff when the connection fails.
+    async fn subscribe(&self, url: &str) {
+        let mut cursor: Option<u64> = None;
+        let mut backoff = RECONNECT_MIN_BACKOFF;
+
+        loop {
+            let result = self.consume(url, &mut cursor, &mut backoff).await;
+            if self.cancellation_token.is_cancelled() {
+                break;
+            }
+            if let Err(err) = result {
+                tracing::warn!(error = ?err, ?backoff, "jetstream connection failed");
+            }
+
+            tokio::select! {
+                () = self.cancellation_token.cancelled() => {
+                    break;
+                },
+                () = tokio::time::sleep(backoff) => {},
+            }
+            backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+        }
+    }
+
+    async fn consume(
+        &self,
+        url: &str,
+        cursor: &mut Option<u64>,
+        backoff: &mut Duration,
+    ) -> Result<()> {
+        let mut url = url::Url::parse(url)?;
+        if let Some(cursor) = cursor {
+            url.query_pairs_mut()
+                .append_pair("cursor", &cursor.saturating_sub(CURSOR_REWIND).to_string());
+        }
+
+        let (mut stream, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+        tracing::info!(url = url.as_str(), "jetstream connected");
+        *backoff = RECONNECT_MIN_BACKOFF;
+
+        loop {
+            let message = tokio::select! {
+                () = self.cancellation_token.cancelled() => {
+                    let _ = stream.close(None).await;
+                    return Ok(());
+                },
+                message = stream.next() => message,
+            };
+
+            match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Err(err) = self.handle_message(&text, cursor).await {
+                        tracing::warn!(error = ?err, "invalid jetstream message");
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(anyhow!("jetstream connection closed"));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+            }
+        }
+    }
+
+    async fn handle_message(&self, text: &str, cursor: &mut Option<u64>) -> Result<()> {
+        let event: JetstreamEvent = serde_json::from_str(text)?;
+        if event.time_us.is_some() {
+            *cursor = event.time_us;
+        }
+
+        if event.kind != "identity" && event.kind != "account" {
+            return Ok(());
+        }
+
+        let handle = event.identity.and_then(|identity| identity.handle);
+        tracing::debug!(
+            did = event.did,
+            kind = event.kind,
+            ?handle,
+            "invalidating DID"
+        );
+        self.verifier
+            .invalidate_cached_did(&event.did, handle.as_deref())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        cache::{subject_cache_key, ResolveHandleResult},
+        did::{DidDocument, DidMethodResolver},
+        identity::Identity,
+        verifier::VerifierOptions,
+    };
+
+    /// Resolves `did:test:<name>` to a document with the handle `<name>.test`.
+    struct TestResolver;
+
+    #[async_trait]
+    impl DidMethodResolver for TestResolver {
+        fn method(&self) -> &str {
+            "test"
+        }
+
+        async fn resolve(&self, did: &str) -> Result<DidDocument> {
+            let name = did.strip_prefix("did:test:").unwrap_or_default();
+            DidDocument::from_value(serde_json::json!({
+                "id": did,
+                "alsoKnownAs": [format!("at://{}.test", name)],
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_events_remove_cached_entries() {
+        let verifier = Verifier::new(&reqwest::Client::new(), VerifierOptions::default())
+            .with_resolver(Arc::new(TestResolver));
+        for did in ["did:test:alice", "did:test:bob"] {
+            verifier.fetch_did_document(did).await.unwrap();
+            verifier
+                .verify_identity(did, &Identity::unsupported("https://example.com/"))
+                .await;
+        }
+        verifier
+            .resolve_handle_cache
+            .insert(
+                subject_cache_key("alice.test"),
+                ResolveHandleResult::Found("did:test:alice".to_string()),
+            )
+            .await;
+
+        let fixture = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/jetstream.jsonl"
+        );
+        let task = JetstreamTask::new(
+            verifier.clone(),
+            JetstreamSource::Replay(fixture.to_string()),
+            CancellationToken::new(),
+        );
+        task.run_background().await.unwrap();
+        verifier.resolve_handle_cache.run_pending_tasks().await;
+        verifier.verify_cache.run_pending_tasks().await;
+
+        let alice = verifier.cache_entries("did:test:alice").await;
+        assert!(!alice.document_cached);
+        assert_eq!(alice.handles, 0);
+        assert!(alice.verifications.is_empty());
+
+        // Commit events don't invalidate anything.
+        let bob = verifier.cache_entries("did:test:bob").await;
+        assert!(bob.document_cached);
+        assert_eq!(bob.verifications.len(), 1);
+    }
+}
@@ -1,10 +1,24 @@
 pub mod cache;
 pub mod config;
 pub mod did;
+pub mod did_key;
 pub mod did_plc;
 pub mod did_web;
+pub mod did_webvh;
 pub mod errors;
+pub mod health;
 pub mod http;
+pub mod i18n;
 pub mod identity;
+pub mod jetstream;
+pub mod monitoring;
+pub mod rate_limit;
+pub mod remedy;
 pub mod resolve;
+pub mod telemetry;
+#[cfg(test)]
+mod test_server;
+pub mod verifier;
+pub mod watch;
+pub mod webhook;
 pub mod worker;
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::{cache::VerifyResult, verifier::CacheSizes};
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs the global metrics recorder, returning the handle used to render `/metrics`.
+pub fn install_recorder() -> Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets(LATENCY_BUCKETS)?
+        .install_recorder()
+        .map_err(|err| anyhow!("installing metrics recorder failed: {}", err))?;
+
+    describe_counter!(
+        "weathervane_verifications_total",
+        "Identity verifications by identity kind and outcome."
+    );
+    describe_histogram!(
+        "weathervane_verification_duration_seconds",
+        "Time taken to verify an identity, by identity kind."
+    );
+    describe_histogram!(
+        "weathervane_handle_resolution_duration_seconds",
+        "Time taken by each handle resolution method, by outcome."
+    );
+    describe_histogram!(
+        "weathervane_did_fetch_duration_seconds",
+        "Time taken to fetch a DID document, by DID method and outcome."
+    );
+    describe_counter!(
+        "weathervane_did_fetch_errors_total",
+        "Failed DID document fetches by DID method."
+    );
+    describe_counter!(
+        "weathervane_cache_requests_total",
+        "Cache lookups by cache and result."
+    );
+    describe_gauge!("weathervane_cache_entries", "Approximate entries by cache.");
+    describe_gauge!(
+        "weathervane_verify_queue_depth",
+        "Verification requests waiting to be picked up."
+    );
+    describe_gauge!("weathervane_sse_streams_active", "Open DID update streams.");
+
+    Ok(handle)
+}
+
+/// Periodically drains histogram samples so memory stays bounded between scrapes.
+pub async fn run_upkeep(handle: PrometheusHandle, cancellation_token: CancellationToken) {
+    loop {
+        tokio::select! {
+            () = cancellation_token.cancelled() => {
+                break;
+            },
+            () = tokio::time::sleep(Duration::from_secs(5)) => {
+                handle.run_upkeep();
+            }
+        }
+    }
+}
+
+pub(crate) fn record_cache_lookup(cache: &'static str, hit: bool) {
+    counter!(
+        "weathervane_cache_requests_total",
+        "cache" => cache,
+        "result" => if hit { "hit" } else { "miss" },
+    )
+    .increment(1);
+}
+
+pub(crate) fn record_verification(kind: &str, result: &VerifyResult, elapsed: Duration) {
+    let outcome = match result {
+        VerifyResult::Found => "verified",
+        VerifyResult::NotFound(_) => "not_found",
+        VerifyResult::Unreachable(_) => "unreachable",
+    };
+    counter!(
+        "weathervane_verifications_total",
+        "kind" => kind.to_string(),
+        "outcome" => outcome,
+    )
+    .increment(1);
+    histogram!(
+        "weathervane_verification_duration_seconds",
+        "kind" => kind.to_string(),
+    )
+    .record(elapsed);
+}
+
+/// Records the latency and outcome of a handle resolution method.
+pub(crate) async fn record_handle_resolution<T>(
+    method: &'static str,
+    resolution: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let started = Instant::now();
+    let result = resolution.await;
+    histogram!(
+        "weathervane_handle_resolution_duration_seconds",
+        "method" => method,
+        "outcome" => outcome(&result),
+    )
+    .record(started.elapsed());
+    result
+}
+
+/// Records the latency and outcome of fetching a DID document.
+pub(crate) async fn record_did_fetch<T>(
+    method: &'static str,
+    fetch: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let started = Instant::now();
+    let result = fetch.await;
+    histogram!(
+        "weathervane_did_fetch_duration_seconds",
+        "method" => method,
+        "outcome" => outcome(&result),
+    )
+    .record(started.elapsed());
+    if result.is_err() {
+        counter!("weathervane_did_fetch_errors_total", "method" => method).increment(1);
+    }
+    result
+}
+
+fn outcome<T>(result: &Result<T>) -> &'static str {
+    if result.is_ok() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+pub(crate) fn set_cache_entries(sizes: &CacheSizes) {
+    gauge!("weathervane_cache_entries", "cache" => "resolve_handle")
+        .set(sizes.resolve_handle as f64);
+    gauge!("weathervane_cache_entries", "cache" => "did_document").set(sizes.did_document as f64);
+    gauge!("weathervane_cache_entries", "cache" => "verify").set(sizes.verify as f64);
+}
+
+pub(crate) fn set_verify_queue_depth(depth: usize) {
+    gauge!("weathervane_verify_queue_depth").set(depth as f64);
+}
+
+/// Counts an open DID update stream until dropped.
+pub(crate) struct ActiveStream;
+
+impl ActiveStream {
+    pub(crate) fn new() -> Self {
+        gauge!("weathervane_sse_streams_active").increment(1);
+        Self
+    }
+}
+
+impl Drop for ActiveStream {
+    fn drop(&mut self) {
+        gauge!("weathervane_sse_streams_active").decrement(1);
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use moka::future::Cache;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Allows `burst` requests at once, refilled evenly over `period`.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl TryFrom<String> for RateLimit {
+    type Error = anyhow::Error;
+
+    /// Parses a limit such as `10/1m`.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (burst, period) = value
+            .split_once('/')
+            .ok_or(anyhow!("rate limit must be formatted as COUNT/PERIOD"))?;
+        let burst = burst.trim().parse::<u32>().map_err(|err| {
+            anyhow::Error::new(err).context(anyhow!("parsing rate limit count failed"))
+        })?;
+        let period = duration_str::parse(period.trim())
+            .map_err(|err| anyhow!("parsing rate limit period failed: {}", err))?;
+        if burst == 0 || period.is_zero() {
+            return Err(anyhow!(
+                "rate limit count and period must be greater than zero"
+            ));
+        }
+        Ok(Self { burst, period })
+    }
+}
+
+/// Request limits applied to visitors and to the hosts contacted while verifying identities.
+#[derive(Clone, Debug)]
+pub struct RateLimits {
+    /// Limits `POST /validate` per client IP.
+    pub validate: Option<RateLimit>,
+    /// Limits `GET /did/:did/updates` per client IP.
+    pub updates: Option<RateLimit>,
+    /// Limits badges, XRPC methods, DID JSON responses and DID re-checks per client IP.
+    pub api: Option<RateLimit>,
+    /// Limits the subjects verified by `POST /api/verify/batch` per client IP.
+    pub batch: Option<RateLimit>,
+    /// Limits verifications that contact a host, per host.
+    pub outbound_host: Option<RateLimit>,
+    /// The header set by a trusted reverse proxy with the client IP, such as `X-Forwarded-For`.
+    pub trusted_proxy_header: Option<String>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            validate: Some(RateLimit {
+                burst: 10,
+                period: Duration::from_secs(60),
+            }),
+            updates: Some(RateLimit {
+                burst: 30,
+                period: Duration::from_secs(60),
+            }),
+            api: Some(RateLimit {
+                burst: 60,
+                period: Duration::from_secs(60),
+            }),
+            batch: Some(RateLimit {
+                burst: 500,
+                period: Duration::from_secs(600),
+            }),
+            outbound_host: Some(RateLimit {
+                burst: 60,
+                period: Duration::from_secs(60),
+            }),
+            trusted_proxy_header: None,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed by client IP or host.
+#[derive(Clone)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    buckets: Cache<String, Arc<Mutex<Bucket>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            // An idle bucket is full again after one period, so it can be dropped.
+            buckets: Cache::builder()
+                .max_capacity(1024 * 100)
+                .time_to_idle(limit.period)
+                .build(),
+        }
+    }
+
+    /// Takes a token for the key, or returns how long until one is available.
+    pub(crate) async fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_tokens(key, 1).await
+    }
+
+    /// Takes `tokens` tokens for the key, or returns how long until they are available. Taking
+    /// more tokens than the burst takes a full bucket.
+    pub(crate) async fn check_tokens(&self, key: &str, tokens: u32) -> Result<(), Duration> {
+        let burst = f64::from(self.limit.burst);
+        let tokens = f64::from(tokens).min(burst);
+        let bucket = self
+            .buckets
+            .get_with_by_ref(key, async {
+                Arc::new(Mutex::new(Bucket {
+                    tokens: burst,
+                    updated: Instant::now(),
+                }))
+            })
+            .await;
+
+        let per_second = burst / self.limit.period.as_secs_f64();
+        let mut bucket = bucket.lock().unwrap();
+        let now = Instant::now();
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated).as_secs_f64() * per_second)
+            .min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= tokens {
+            bucket.tokens -= tokens;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (tokens - bucket.tokens) / per_second,
+            ))
+        }
+    }
+
+    /// Waits until a token for the key is available and takes it.
+    pub(crate) async fn acquire(&self, key: &str) {
+        while let Err(retry_after) = self.check(key).await {
+            tokio::time::sleep(retry_after).await;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+use crate::{i18n::Reason, identity::Identity};
+
+/// A DNS TXT record to publish.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TxtRecord {
+    pub name: String,
+    pub value: String,
+}
+
+/// What to change so that an identity that was not verified references the DID, built from the
+/// reason it was not verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remedy {
+    /// The change to make, as a message of the catalogs.
+    pub step: Reason,
+    pub record: Option<TxtRecord>,
+    /// Text to publish as is, such as a `link` element.
+    pub snippet: Option<String>,
+    /// What the identity references now.
+    pub found: Option<String>,
+    /// What the identity needs to reference.
+    pub expected: Option<String>,
+}
+
+impl Remedy {
+    fn new(step: Reason) -> Self {
+        Self {
+            step,
+            record: None,
+            snippet: None,
+            found: None,
+            expected: None,
+        }
+    }
+
+    fn record(mut self, name: String, value: String) -> Self {
+        self.record = Some(TxtRecord { name, value });
+        self
+    }
+
+    fn snippet(mut self, snippet: String) -> Self {
+        self.snippet = Some(snippet);
+        self
+    }
+
+    /// Compares what was found with what was expected. Empty values are shown as missing.
+    fn compare(mut self, found: Option<String>, expected: String) -> Self {
+        self.found = found.filter(|found| !found.is_empty());
+        self.expected = Some(expected);
+        self
+    }
+
+    /// The remedy for an identity of `did` that was not verified, or `None` for identity kinds
+    /// and reasons without one. `handle` is the handle in the DID document, which the GitHub
+    /// profile needs to link to.
+    pub fn for_failure(
+        did: &str,
+        handle: Option<&str>,
+        identity: &Identity,
+        reason: &Reason,
+    ) -> Option<Self> {
+        match (identity.kind.as_str(), reason.id) {
+            ("handle", "reason-handle-mismatch" | "reason-resolve-failed") => {
+                let handle = identity.value.strip_prefix("at://")?;
+                let name = format!("_atproto.{}", handle);
+                Some(
+                    Self::new(
+                        Reason::new("remedy-handle-txt")
+                            .arg("name", name.as_str())
+                            .arg("handle", handle),
+                    )
+                    .record(name, format!("did={}", did))
+                    .compare(reason.get("found").map(str::to_string), did.to_string()),
+                )
+            }
+            ("domain", "reason-txt-mismatch" | "reason-txt-lookup-failed") => {
+                let domain = reason.get("domain")?;
+                Some(
+                    Self::new(Reason::new("remedy-domain-txt").arg("domain", domain))
+                        .record(domain.to_string(), format!("did={}", did))
+                        .compare(
+                            reason.get("found").map(|found| format!("did={}", found)),
+                            format!("did={}", did),
+                        ),
+                )
+            }
+            ("did_method_plc" | "did_method_web", "reason-also-known-as-missing") => Some(
+                Self::new(
+                    Reason::new("remedy-also-known-as")
+                        .arg("value", identity.value.as_str())
+                        .arg("did", did),
+                )
+                .snippet(format!("\"alsoKnownAs\": [\n  \"{}\"\n]", did))
+                .compare(reason.get("found").map(str::to_string), did.to_string()),
+            ),
+            ("did_method_plc" | "did_method_web", "reason-resolve-failed") => Some(Self::new(
+                Reason::new("remedy-resolve").arg("value", identity.value.as_str()),
+            )),
+            (
+                "github",
+                "reason-github-no-bluesky" | "reason-handle-mismatch" | "reason-resolve-failed",
+            ) => {
+                let handle = match handle {
+                    Some(handle) => handle,
+                    None => return Some(Self::new(Reason::new("remedy-github-no-handle"))),
+                };
+                let profile = |handle: &str| format!("https://bsky.app/profile/{}", handle);
+                // The social account links to a handle that resolves to another DID, or to none.
+                let found = match reason.id {
+                    "reason-github-no-bluesky" => None,
+                    _ => reason
+                        .get("handle")
+                        .or_else(|| reason.get("value"))
+                        .map(profile),
+                };
+                Some(
+                    Self::new(
+                        Reason::new("remedy-github-social").arg("user", identity.value.as_str()),
+                    )
+                    .snippet(profile(handle))
+                    .compare(found, profile(handle)),
+                )
+            }
+            (
+                "website",
+                "reason-website-no-link"
+                | "reason-website-fetch-failed"
+                | "reason-website-read-failed",
+            ) => {
+                let step = match reason.id {
+                    "reason-website-no-link" => "remedy-website-link",
+                    _ => "remedy-website-fetch",
+                };
+                let url = reason.get("url").unwrap_or(&identity.value);
+                Some(
+                    Self::new(Reason::new(step).arg("url", url))
+                        .snippet(format!("<link rel=\"did\" href=\"{}\">", did))
+                        .compare(reason.get("found").map(str::to_string), did.to_string()),
+                )
+            }
+            ("website", "reason-website-unsupported") => {
+                Some(Self::new(Reason::new("remedy-website-url")))
+            }
+            _ => None,
+        }
+    }
+}
@@ -7,14 +7,17 @@ use hickory_resolver::{
 use std::collections::HashSet;
 use std::time::Duration;
 
-use crate::did_web::web_query_simple;
+use crate::{
+    did::did_method, did_web::web_query_simple, errors::is_transient_error,
+    monitoring::record_handle_resolution,
+};
 
 pub(crate) enum InputType {
     Handle(String),
-    Plc(String),
-    Web(String),
+    Did(String),
 }
 
+#[tracing::instrument]
 pub async fn resolve_handle_dns(lookup_dns: &str) -> Result<String> {
     let resolver = AsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default());
 
@@ -40,6 +43,7 @@ pub async fn resolve_handle_dns(lookup_dns: &str) -> Result<String> {
         .ok_or(anyhow!("No records found: {}", lookup_dns))
 }
 
+#[tracing::instrument(skip(http_client))]
 pub async fn resolve_handle_http(http_client: &reqwest::Client, handle: &str) -> Result<String> {
     let lookup_url = format!("https://{}/.well-known/atproto-did", handle);
 
@@ -48,6 +52,7 @@ pub async fn resolve_handle_http(http_client: &reqwest::Client, handle: &str) ->
         .timeout(Duration::from_secs(10))
         .send()
         .await?
+        .error_for_status()?
         .text()
         .await
         .map_err(|err| err.into())
@@ -60,6 +65,7 @@ pub async fn resolve_handle_http(http_client: &reqwest::Client, handle: &str) ->
         })
 }
 
+#[tracing::instrument(skip(http_client))]
 pub async fn resolve_did_web(http_client: &reqwest::Client, handle: &str) -> Result<String> {
     let lookup_url = format!("https://{}/.well-known/atproto-did", handle);
 
@@ -68,6 +74,7 @@ pub async fn resolve_did_web(http_client: &reqwest::Client, handle: &str) -> Res
         .timeout(Duration::from_secs(10))
         .send()
         .await?
+        .error_for_status()?
         .text()
         .await
         .map_err(|err| err.into())
@@ -93,15 +100,17 @@ pub(crate) fn parse_input(input: &str) -> Result<InputType> {
     if trimmed.is_empty() {
         return Err(anyhow!("Invalid handle or DID"));
     }
-    if trimmed.starts_with("did:web:") {
-        Ok(InputType::Web(trimmed.to_string()))
-    } else if trimmed.starts_with("did:plc:") {
-        Ok(InputType::Plc(trimmed.to_string()))
+    if trimmed.starts_with("did:") {
+        if did_method(trimmed).is_none() {
+            return Err(anyhow!("Invalid DID"));
+        }
+        Ok(InputType::Did(trimmed.to_string()))
     } else {
         Ok(InputType::Handle(trimmed.to_string()))
     }
 }
 
+#[tracing::instrument(skip(http_client))]
 pub async fn resolve_handle(http_client: &reqwest::Client, handle: &str) -> Result<String> {
     let trimmed = {
         if let Some(value) = handle.trim().strip_prefix("at://") {
@@ -114,18 +123,24 @@ pub async fn resolve_handle(http_client: &reqwest::Client, handle: &str) -> Resu
     };
 
     let (dns_lookup, http_lookup, did_web_lookup) = join3(
-        resolve_handle_dns(&format!("_atproto.{}", trimmed)),
-        resolve_handle_http(http_client, trimmed),
-        web_query_simple(http_client, trimmed),
+        record_handle_resolution("dns", resolve_handle_dns(&format!("_atproto.{}", trimmed))),
+        record_handle_resolution("http", resolve_handle_http(http_client, trimmed)),
+        record_handle_resolution("did_web", web_query_simple(http_client, trimmed)),
     )
     .await;
 
-    let results = vec![dns_lookup, http_lookup, did_web_lookup]
-        .into_iter()
-        .filter_map(|result| result.ok())
-        .collect::<Vec<String>>();
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    for lookup in [dns_lookup, http_lookup, did_web_lookup] {
+        match lookup {
+            Ok(did) => results.push(did),
+            Err(err) => failures.push(err),
+        }
+    }
     if results.is_empty() {
-        return Err(anyhow!("Failed to resolve handle {}", handle));
+        return Err(
+            lookup_failure(failures).context(format!("Failed to resolve handle {}", handle))
+        );
     }
 
     let first = results[0].clone();
@@ -138,9 +153,47 @@ pub async fn resolve_handle(http_client: &reqwest::Client, handle: &str) -> Resu
     ))
 }
 
+/// The error of a handle that no lookup resolved. A transient failure is preferred, because the
+/// handle may resolve once it goes away.
+fn lookup_failure(failures: Vec<anyhow::Error>) -> anyhow::Error {
+    let transient = failures.iter().position(is_transient_error).unwrap_or(0);
+    failures
+        .into_iter()
+        .nth(transient)
+        .unwrap_or_else(|| anyhow!("No handle lookups were made"))
+}
+
 pub async fn resolve_subject(http_client: &reqwest::Client, subject: &str) -> Result<String> {
     match parse_input(subject)? {
         InputType::Handle(handle) => resolve_handle(http_client, &handle).await,
-        InputType::Plc(did) | InputType::Web(did) => Ok(did),
+        InputType::Did(did) => Ok(did),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+
+    use super::*;
+    use crate::test_server::serve_failures;
+
+    async fn fetch(path: &str) -> anyhow::Error {
+        let base = serve_failures(Router::new()).await;
+        let response = reqwest::get(format!("{}{}", base, path)).await.unwrap();
+        response.error_for_status().unwrap_err().into()
+    }
+
+    #[tokio::test]
+    async fn handle_lookups_keep_transient_failures() {
+        let failures = vec![anyhow!("No records found"), fetch("/busy").await];
+        let err = lookup_failure(failures).context("Failed to resolve handle example.com");
+        assert!(is_transient_error(&err));
+    }
+
+    #[tokio::test]
+    async fn handle_lookups_without_transient_failures_are_definitive() {
+        let failures = vec![anyhow!("No records found"), fetch("/missing").await];
+        let err = lookup_failure(failures).context("Failed to resolve handle example.com");
+        assert!(!is_transient_error(&err));
     }
 }
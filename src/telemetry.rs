@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use tracing_subscriber::{prelude::*, EnvFilter, Layer};
+
+/// Flushes exported spans when dropped.
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("shutting down trace exporter failed: {}", err);
+            }
+        }
+    }
+}
+
+/// Installs the global tracing subscriber.
+///
+/// `LOG_FORMAT` selects `pretty` (the default) or `json` logs. With the `otlp` feature, spans are
+/// also exported when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+pub fn init() -> Result<Telemetry> {
+    let fmt_layer = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("pretty") | Err(_) => tracing_subscriber::fmt::layer().pretty().boxed(),
+        Ok("json") => tracing_subscriber::fmt::layer().json().boxed(),
+        Ok(log_format) => {
+            return Err(anyhow!(
+                "LOG_FORMAT must be pretty or json, not {}",
+                log_format
+            ))
+        }
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(EnvFilter::new(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "weathervane=debug,info".into()),
+        ));
+
+    #[cfg(feature = "otlp")]
+    {
+        let provider = otlp::tracer_provider()?;
+        let otlp_layer = provider.as_ref().map(otlp::layer);
+        registry.with(otlp_layer).init();
+        Ok(Telemetry { provider })
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        registry.init();
+        Ok(Telemetry {})
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use anyhow::Result;
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::{
+        runtime,
+        trace::{Tracer, TracerProvider},
+        Resource,
+    };
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// Builds a tracer provider exporting to `OTEL_EXPORTER_OTLP_ENDPOINT` over HTTP, if set.
+    pub(super) fn tracer_provider() -> Result<Option<TracerProvider>> {
+        if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_err() {
+            return Ok(None);
+        }
+
+        let exporter = SpanExporter::builder().with_http().build()?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new(
+                "service.name",
+                std::env::var("OTEL_SERVICE_NAME").unwrap_or("weathervane".to_string()),
+            )]))
+            .build();
+        Ok(Some(provider))
+    }
+
+    pub(super) fn layer<S>(provider: &TracerProvider) -> OpenTelemetryLayer<S, Tracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("weathervane"))
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{http::StatusCode, routing::get, Router};
+use std::time::Duration;
+
+/// Serves `router` on a local port and returns its base URL.
+pub(crate) async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", address)
+}
+
+/// A server with `/busy` (503), `/missing` (404) and `/slow` (a response after 5 seconds) routes,
+/// along with `routes`.
+pub(crate) async fn serve_failures(routes: Router) -> String {
+    serve(
+        routes
+            .route("/busy", get(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    StatusCode::OK
+                }),
+            ),
+    )
+    .await
+}
@@ -0,0 +1,499 @@
+use anyhow::Result;
+use futures_util::stream::Stream;
+use moka::future::Cache;
+use serde::Serialize;
+use std::{collections::BTreeSet, sync::Arc, time::Instant};
+use utoipa::ToSchema;
+
+use crate::{
+    cache::{
+        did_document_cache_key, did_document_cached, new_did_document_cache,
+        new_resolve_handle_cache, new_worker_cache, resolve_subject_cached, split_verify_cache_key,
+        subject_cache_key, verify_cache_key, CacheConfig, DidDocumentResult, ResolveHandleResult,
+        VerifyCacheEntry, VerifyResult,
+    },
+    did::{DidDocument, DidMethodResolver, DidMethodResolvers},
+    i18n::Reason,
+    identity::{Identity, IdentityValidator, IdentityValidators},
+    monitoring::{record_cache_lookup, record_verification},
+    rate_limit::{RateLimit, RateLimiter},
+};
+
+/// Options used to construct a [`Verifier`].
+#[derive(Clone, Debug)]
+pub struct VerifierOptions {
+    pub plc_hostname: String,
+    pub cache: CacheConfig,
+    /// Limits the DID document fetches, handle resolutions and identity verifications that
+    /// contact a host, per host. Lookups wait when over the limit, verifications are reported as
+    /// unreachable.
+    pub host_rate_limit: Option<RateLimit>,
+}
+
+impl Default for VerifierOptions {
+    fn default() -> Self {
+        Self {
+            plc_hostname: "plc.directory".to_string(),
+            cache: CacheConfig::default(),
+            host_rate_limit: None,
+        }
+    }
+}
+
+/// The outcome of verifying a DID document and each of its identities.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VerificationEvent {
+    /// The DID document was resolved and the listed identities will be verified.
+    Document {
+        did: String,
+        identities: Vec<Identity>,
+    },
+    Verified {
+        identity: Identity,
+    },
+    Unverified {
+        identity: Identity,
+        reason: String,
+    },
+    /// The DID document could not be resolved.
+    Failed {
+        reason: String,
+    },
+}
+
+/// The approximate number of entries in each cache.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CacheSizes {
+    pub resolve_handle: u64,
+    pub did_document: u64,
+    pub verify: u64,
+}
+
+/// The cached entries of a DID.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct DidCacheEntries {
+    pub did: String,
+    pub document_cached: bool,
+    /// The cached error when resolving the DID document failed.
+    pub document_error: Option<String>,
+    /// The number of cached handle resolutions that resolved to the DID.
+    pub handles: usize,
+    pub verifications: Vec<CachedVerification>,
+}
+
+/// A cached verification result. The identity is known when the DID document is cached.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CachedVerification {
+    pub key: String,
+    pub identity: Option<Identity>,
+    pub verified: bool,
+    pub reason: Option<String>,
+}
+
+/// Resolves subjects and DID documents and verifies identities, caching results.
+///
+/// Clones share the same caches.
+#[derive(Clone)]
+pub struct Verifier {
+    http_client: reqwest::Client,
+    did_method_resolvers: DidMethodResolvers,
+    identity_validators: IdentityValidators,
+    cache_config: CacheConfig,
+    host_limiter: Option<RateLimiter>,
+    pub(crate) resolve_handle_cache: Cache<String, ResolveHandleResult>,
+    pub(crate) did_document_cache: Cache<String, DidDocumentResult>,
+    pub(crate) verify_cache: Cache<String, VerifyCacheEntry>,
+    /// The DIDs that were resolved, looked up or verified within the longest cache TTL, so that
+    /// events about other DIDs don't have to scan the caches.
+    cached_dids: Cache<String, ()>,
+}
+
+impl Verifier {
+    pub fn new(http_client: &reqwest::Client, options: VerifierOptions) -> Self {
+        Self {
+            http_client: http_client.clone(),
+            did_method_resolvers: DidMethodResolvers::default_resolvers(
+                http_client,
+                &options.plc_hostname,
+            ),
+            identity_validators: IdentityValidators::default_validators(
+                http_client,
+                &options.plc_hostname,
+            ),
+            resolve_handle_cache: new_resolve_handle_cache(&options.cache),
+            did_document_cache: new_did_document_cache(&options.cache),
+            verify_cache: new_worker_cache(&options.cache),
+            cached_dids: Cache::builder()
+                .max_capacity(
+                    options.cache.resolve_handle_capacity
+                        + options.cache.did_document_capacity
+                        + options.cache.verify_capacity,
+                )
+                .time_to_live(options.cache.longest_ttl())
+                .build(),
+            cache_config: options.cache,
+            host_limiter: options.host_rate_limit.map(RateLimiter::new),
+        }
+    }
+
+    pub fn with_resolver(mut self, resolver: Arc<dyn DidMethodResolver>) -> Self {
+        self.did_method_resolvers = self.did_method_resolvers.with_resolver(resolver);
+        self
+    }
+
+    pub fn with_validator(mut self, validator: Arc<dyn IdentityValidator>) -> Self {
+        self.identity_validators = self.identity_validators.with_validator(validator);
+        self
+    }
+
+    pub fn identity_validators(&self) -> &IdentityValidators {
+        &self.identity_validators
+    }
+
+    pub fn cache_config(&self) -> &CacheConfig {
+        &self.cache_config
+    }
+
+    /// Resolves a handle or DID into a DID.
+    pub async fn resolve_subject(&self, subject: &str) -> Result<String> {
+        let did = resolve_subject_cached(
+            self.resolve_handle_cache.clone(),
+            &self.http_client,
+            self.host_limiter.as_ref(),
+            subject,
+        )
+        .await?;
+        self.cached_dids.insert(did.clone(), ()).await;
+        Ok(did)
+    }
+
+    pub async fn fetch_did_document(&self, did: &str) -> Result<DidDocument> {
+        self.cached_dids.insert(did.to_string(), ()).await;
+        did_document_cached(
+            self.did_document_cache.clone(),
+            &self.did_method_resolvers,
+            self.host_limiter.as_ref(),
+            did,
+        )
+        .await
+    }
+
+    /// Returns the cached DID document without resolving it.
+    pub(crate) async fn cached_did_document(&self, did: &str) -> Option<DidDocument> {
+        match self
+            .did_document_cache
+            .get(&did_document_cache_key(did))
+            .await
+        {
+            Some(DidDocumentResult::Found(document)) => Some(document),
+            _ => None,
+        }
+    }
+
+    /// Fetches the DID document, replacing any cached document.
+    pub async fn refresh_did_document(&self, did: &str) -> Result<DidDocument> {
+        self.did_document_cache
+            .invalidate(&did_document_cache_key(did))
+            .await;
+        self.fetch_did_document(did).await
+    }
+
+    /// Removes the cached DID document, handle resolutions and verification results of a DID.
+    ///
+    /// Handles that resolved to the DID are removed along with the new handle, if given, so a
+    /// handle that moved to or away from the DID is resolved again.
+    pub async fn invalidate_did(&self, did: &str, handle: Option<&str>) -> Result<()> {
+        self.did_document_cache
+            .invalidate(&did_document_cache_key(did))
+            .await;
+
+        self.resolve_handle_cache
+            .invalidate(&subject_cache_key(did))
+            .await;
+        if let Some(handle) = handle {
+            self.invalidate_handle(handle).await;
+        }
+
+        let resolved_did = did.to_string();
+        self.resolve_handle_cache.invalidate_entries_if(move |_, value| {
+            matches!(value, ResolveHandleResult::Found(found) if found == &resolved_did)
+        })?;
+
+        let verified_did = did.to_string();
+        self.verify_cache.invalidate_entries_if(move |key, _| {
+            split_verify_cache_key(key).is_some_and(|(did, _)| did == verified_did)
+        })?;
+
+        self.cached_dids.invalidate(did).await;
+        Ok(())
+    }
+
+    /// Removes the cached entries of a DID like [`Verifier::invalidate_did`], but only scans the
+    /// caches when the DID has cached entries. The new handle is always resolved again, since it
+    /// may have been cached before it moved to the DID.
+    pub(crate) async fn invalidate_cached_did(
+        &self,
+        did: &str,
+        handle: Option<&str>,
+    ) -> Result<()> {
+        if self.cached_dids.contains_key(did) {
+            return self.invalidate_did(did, handle).await;
+        }
+        if let Some(handle) = handle {
+            self.invalidate_handle(handle).await;
+        }
+        Ok(())
+    }
+
+    async fn invalidate_handle(&self, handle: &str) {
+        for subject in [
+            handle.to_string(),
+            format!("@{}", handle),
+            format!("at://{}", handle),
+        ] {
+            self.resolve_handle_cache
+                .invalidate(&subject_cache_key(&subject))
+                .await;
+        }
+    }
+
+    pub async fn cache_sizes(&self) -> CacheSizes {
+        self.resolve_handle_cache.run_pending_tasks().await;
+        self.did_document_cache.run_pending_tasks().await;
+        self.verify_cache.run_pending_tasks().await;
+        CacheSizes {
+            resolve_handle: self.resolve_handle_cache.entry_count(),
+            did_document: self.did_document_cache.entry_count(),
+            verify: self.verify_cache.entry_count(),
+        }
+    }
+
+    /// Returns the cached entries of a DID.
+    pub async fn cache_entries(&self, did: &str) -> DidCacheEntries {
+        let document = self
+            .did_document_cache
+            .get(&did_document_cache_key(did))
+            .await;
+        let identities = match &document {
+            Some(DidDocumentResult::Found(document)) => self.parse_identities(document),
+            _ => Vec::new(),
+        };
+
+        let handles = self
+            .resolve_handle_cache
+            .iter()
+            .filter(|(_, value)| matches!(value, ResolveHandleResult::Found(found) if found == did))
+            .count();
+
+        let mut verifications = self
+            .verify_cache
+            .iter()
+            .filter_map(|(key, value)| {
+                let identity_key = match split_verify_cache_key(&key) {
+                    Some((key_did, identity_key)) if key_did == did => identity_key,
+                    _ => return None,
+                };
+                Some(CachedVerification {
+                    key: identity_key.to_string(),
+                    identity: identities
+                        .iter()
+                        .find(|identity| identity.to_key() == identity_key)
+                        .cloned(),
+                    verified: value.result == VerifyResult::Found,
+                    reason: value.result.reason().map(|reason| reason.to_string()),
+                })
+            })
+            .collect::<Vec<CachedVerification>>();
+        verifications.sort_by(|a, b| a.key.cmp(&b.key));
+
+        DidCacheEntries {
+            did: did.to_string(),
+            document_cached: document.is_some(),
+            document_error: match document {
+                Some(DidDocumentResult::NotFound(err))
+                | Some(DidDocumentResult::Unreachable(err)) => Some(err),
+                _ => None,
+            },
+            handles,
+            verifications,
+        }
+    }
+
+    /// Returns the cached entries of every DID with a cached document or verification result.
+    pub async fn all_cache_entries(&self) -> Vec<DidCacheEntries> {
+        let mut dids = BTreeSet::new();
+        for (_, value) in self.did_document_cache.iter() {
+            if let DidDocumentResult::Found(document) = value {
+                dids.insert(document.id.clone());
+            }
+        }
+        for (key, _) in self.verify_cache.iter() {
+            if let Some((did, _)) = split_verify_cache_key(&key) {
+                dids.insert(did.to_string());
+            }
+        }
+
+        let mut entries = Vec::with_capacity(dids.len());
+        for did in dids {
+            entries.push(self.cache_entries(&did).await);
+        }
+        entries
+    }
+
+    pub fn parse_identities(&self, document: &DidDocument) -> Vec<Identity> {
+        Vec::from_iter(
+            self.identity_validators
+                .parse_identities(&document.also_known_as),
+        )
+    }
+
+    /// Verifies that an identity references the DID.
+    ///
+    /// Concurrent verifications of the same identity and DID wait for a single result. When the
+    /// host the identity is verified against is over its rate limit, the result is
+    /// `Unreachable` so that it is retried once the short transient TTL expires, rather than
+    /// holding a verification worker until the host can be contacted again.
+    #[tracing::instrument(
+        skip(self, identity),
+        fields(identity.kind = identity.kind, identity.value = identity.value)
+    )]
+    pub async fn verify_identity(&self, did: &str, identity: &Identity) -> VerifyResult {
+        self.cached_dids.insert(did.to_string(), ()).await;
+        let mut miss = false;
+        let entry = self
+            .verify_cache
+            .get_with(verify_cache_key(did, identity), async {
+                miss = true;
+                if let Some(reason) = self.host_rate_limited(identity).await {
+                    return VerifyCacheEntry {
+                        kind: identity.kind.clone(),
+                        result: VerifyResult::Unreachable(reason),
+                    };
+                }
+                let started = Instant::now();
+                let result = self.identity_validators.verify(did, identity).await;
+                record_verification(&identity.kind, &result, started.elapsed());
+                VerifyCacheEntry {
+                    kind: identity.kind.clone(),
+                    result,
+                }
+            })
+            .await;
+        record_cache_lookup("verify", !miss);
+        entry.result
+    }
+
+    /// Takes a token for the host the identity is verified against, or returns why the
+    /// identity can't be verified yet.
+    async fn host_rate_limited(&self, identity: &Identity) -> Option<Reason> {
+        let host_limiter = self.host_limiter.as_ref()?;
+        let host = self.identity_validators.target_host(identity)?;
+        match host_limiter.check(&host).await {
+            Ok(()) => None,
+            Err(_) => {
+                tracing::debug!(host, "host rate limited");
+                Some(Reason::new("reason-host-rate-limited").arg("host", host.as_str()))
+            }
+        }
+    }
+
+    /// Verifies that an identity references the DID, replacing any cached result.
+    pub async fn refresh_identity(&self, did: &str, identity: &Identity) -> VerifyResult {
+        self.verify_cache
+            .invalidate(&verify_cache_key(did, identity))
+            .await;
+        self.verify_identity(did, identity).await
+    }
+
+    /// Returns the event for the outcome of verifying an identity.
+    pub async fn verify_identity_event(&self, did: &str, identity: Identity) -> VerificationEvent {
+        match self.verify_identity(did, &identity).await {
+            VerifyResult::Found => VerificationEvent::Verified { identity },
+            VerifyResult::NotFound(reason) | VerifyResult::Unreachable(reason) => {
+                VerificationEvent::Unverified {
+                    identity,
+                    reason: reason.to_string(),
+                }
+            }
+        }
+    }
+
+    /// Resolves the DID document and verifies each of its identities.
+    ///
+    /// The stream yields a `Document` or `Failed` event, followed by one event per identity.
+    pub fn verify_all(&self, did: &str) -> impl Stream<Item = VerificationEvent> + Send + 'static {
+        let verifier = self.clone();
+        let did = did.to_string();
+        async_stream::stream! {
+            let document = match verifier.fetch_did_document(&did).await {
+                Ok(document) => document,
+                Err(err) => {
+                    yield VerificationEvent::Failed { reason: err.to_string() };
+                    return;
+                }
+            };
+
+            let identities = verifier.parse_identities(&document);
+            yield VerificationEvent::Document {
+                did: document.id.clone(),
+                identities: identities.clone(),
+            };
+
+            for identity in identities {
+                yield verifier.verify_identity_event(&document.id, identity).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn cache_verification(verifier: &Verifier, did: &str) {
+        let identity = Identity::unsupported("https://example.com/");
+        verifier
+            .verify_cache
+            .insert(
+                verify_cache_key(did, &identity),
+                VerifyCacheEntry {
+                    kind: identity.kind,
+                    result: VerifyResult::Found,
+                },
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    async fn invalidating_a_did_keeps_dids_it_prefixes() {
+        let verifier = Verifier::new(&reqwest::Client::new(), VerifierOptions::default());
+        cache_verification(&verifier, "did:web:example").await;
+        cache_verification(&verifier, "did:web:example-two.com").await;
+
+        let other = verifier.cache_entries("did:web:example-two.com").await;
+        assert_eq!(
+            verifier
+                .cache_entries("did:web:example")
+                .await
+                .verifications
+                .len(),
+            1
+        );
+        assert_eq!(other.verifications.len(), 1);
+
+        verifier
+            .invalidate_did("did:web:example", None)
+            .await
+            .unwrap();
+        verifier.verify_cache.run_pending_tasks().await;
+
+        assert!(verifier
+            .cache_entries("did:web:example")
+            .await
+            .verifications
+            .is_empty());
+        let other = verifier.cache_entries("did:web:example-two.com").await;
+        assert_eq!(other.verifications.len(), 1);
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::Result;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::time::Instant;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::{
+    verifier::Verifier,
+    webhook::{StatusChange, StatusChangeEvent, WebhookNotifier},
+    worker::{verify_queued, QueueWork, VerifyWork},
+};
+
+/// The DIDs to watch, from configuration and an optional file or URL with one DID per line.
+#[derive(Clone)]
+pub struct WatchList {
+    http_client: reqwest::Client,
+    dids: Vec<String>,
+    source: Option<String>,
+}
+
+impl WatchList {
+    pub fn new(http_client: &reqwest::Client, dids: Vec<String>, source: Option<String>) -> Self {
+        Self {
+            http_client: http_client.clone(),
+            dids,
+            source,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dids.is_empty() && self.source.is_none()
+    }
+
+    pub async fn load(&self) -> Result<Vec<String>> {
+        let mut dids = self.dids.clone();
+
+        let contents = match self.source.as_deref() {
+            Some(source) if source.starts_with("https://") || source.starts_with("http://") => {
+                self.http_client
+                    .get(source)
+                    .timeout(Duration::from_secs(10))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?
+            }
+            Some(source) => std::fs::read_to_string(source)?,
+            None => return Ok(dids),
+        };
+
+        for line in contents.lines().map(|line| line.trim()) {
+            if line.starts_with("did:") && !dids.iter().any(|did| did == line) {
+                dids.push(line.to_string());
+            }
+        }
+        Ok(dids)
+    }
+}
+
+/// Periodically re-verifies watched DIDs, keeping their cached DID documents and verification
+/// results warm, and sends webhooks when an identity changes between verified and unverified.
+///
+/// Each DID is refreshed shortly before its cached results expire, or every `interval` if that
+/// is shorter, with jitter to spread load. The watch list is reloaded every `interval`.
+pub struct WatchTask {
+    verifier: Verifier,
+    verify_work_tx: Sender<QueueWork>,
+    notifier: WebhookNotifier,
+    watch_list: WatchList,
+    interval: Duration,
+    cancellation_token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl WatchTask {
+    pub fn new(
+        verifier: Verifier,
+        verify_work_tx: Sender<QueueWork>,
+        notifier: WebhookNotifier,
+        watch_list: WatchList,
+        interval: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Self {
+            verifier,
+            verify_work_tx,
+            notifier,
+            watch_list,
+            interval,
+            cancellation_token,
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    pub async fn run_background(&self) -> Result<()> {
+        tracing::debug!("WatchTask started");
+
+        // The last known status of each identity, keyed by the watched DID and identity key.
+        let mut statuses: HashMap<(String, String), bool> = HashMap::new();
+        // When each watched DID is next refreshed.
+        let mut schedule: HashMap<String, Instant> = HashMap::new();
+        let mut reload = tokio::time::interval(self.interval);
+
+        loop {
+            let next_due = schedule.values().min().copied();
+            let sleep = async move {
+                match next_due {
+                    Some(next_due) => tokio::time::sleep_until(next_due).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                () = self.cancellation_token.cancelled() => {
+                    break;
+                },
+                _ = reload.tick() => {
+                    self.reload(&mut schedule, &mut statuses).await;
+                },
+                () = sleep => {
+                    let now = Instant::now();
+                    let due = schedule
+                        .iter()
+                        .filter(|(_, next_due)| **next_due <= now)
+                        .map(|(did, _)| did.clone())
+                        .collect::<Vec<String>>();
+                    for did in due {
+                        if self.cancellation_token.is_cancelled() {
+                            break;
+                        }
+                        if let Err(err) = self.check(&did, &mut statuses).await {
+                            tracing::warn!(did, error = ?err, "watched DID check failed");
+                        }
+                        schedule.insert(did, Instant::now() + self.next_delay());
+                    }
+                }
+            }
+        }
+
+        self.tracker.close();
+        self.tracker.wait().await;
+
+        tracing::debug!("WatchTask stopped");
+
+        Ok(())
+    }
+
+    /// The time between refreshes of a DID, ending before the cached results expire.
+    fn refresh_period(&self) -> Duration {
+        (self.verifier.cache_config().shortest_found_ttl() * 4 / 5).min(self.interval)
+    }
+
+    fn next_delay(&self) -> Duration {
+        let period = self.refresh_period();
+        let jitter = rand::thread_rng().gen_range(0..=period.as_millis() as u64 / 10);
+        period - Duration::from_millis(jitter)
+    }
+
+    async fn reload(
+        &self,
+        schedule: &mut HashMap<String, Instant>,
+        statuses: &mut HashMap<(String, String), bool>,
+    ) {
+        let dids = match self.watch_list.load().await {
+            Ok(dids) => dids,
+            Err(err) => {
+                tracing::warn!(error = ?err, "loading watch list failed");
+                return;
+            }
+        };
+
+        schedule.retain(|did, _| dids.contains(did));
+        statuses.retain(|(did, _), _| dids.contains(did));
+
+        // New DIDs are spread over the refresh period.
+        let period = self.refresh_period().as_millis() as u64;
+        for did in dids {
+            schedule.entry(did).or_insert_with(|| {
+                Instant::now() + Duration::from_millis(rand::thread_rng().gen_range(0..=period))
+            });
+        }
+    }
+
+    async fn check(&self, did: &str, statuses: &mut HashMap<(String, String), bool>) -> Result<()> {
+        let document = self.verifier.refresh_did_document(did).await?;
+        let identities = self.verifier.parse_identities(&document);
+        let results = verify_queued(&self.verify_work_tx, &document.id, identities, true).await?;
+
+        let mut changes = Vec::new();
+        for work in results {
+            // A transient failure says nothing about the identity, so the previous status is kept
+            // until the identity can be checked again.
+            let (identity, reason) = match work {
+                VerifyWork::Unreachable(identity, reason) => {
+                    tracing::debug!(
+                        did,
+                        identity = identity.value,
+                        reason = reason.to_string(),
+                        "identity could not be checked"
+                    );
+                    continue;
+                }
+                work => match work.into_outcome() {
+                    Some(outcome) => outcome,
+                    None => continue,
+                },
+            };
+            let verified = reason.is_none();
+            let status_key = (did.to_string(), identity.to_key());
+            if statuses
+                .insert(status_key, verified)
+                .is_some_and(|previous| previous != verified)
+            {
+                tracing::info!(
+                    did,
+                    identity = identity.value,
+                    verified,
+                    "identity status changed"
+                );
+                changes.push(StatusChange {
+                    identity,
+                    verified,
+                    reason: reason.map(|reason| reason.to_string()),
+                });
+            }
+        }
+
+        if changes.is_empty() || self.notifier.is_empty() {
+            return Ok(());
+        }
+
+        let event = StatusChangeEvent {
+            did: document.id,
+            timestamp: chrono::Utc::now(),
+            changes,
+        };
+        let notifier = self.notifier.clone();
+        self.tracker.spawn(async move {
+            if let Err(err) = notifier.notify(&event).await {
+                tracing::error!(error = ?err, "webhook notification failed");
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::identity::Identity;
+
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+pub const SIGNATURE_HEADER: &str = "x-weathervane-signature";
+
+/// An identity whose verification status changed since the previous check.
+#[derive(Clone, Debug, Serialize)]
+pub struct StatusChange {
+    pub identity: Identity,
+    pub verified: bool,
+    pub reason: Option<String>,
+}
+
+/// The JSON payload posted to webhook URLs.
+#[derive(Clone, Debug, Serialize)]
+pub struct StatusChangeEvent {
+    pub did: String,
+    pub timestamp: DateTime<Utc>,
+    pub changes: Vec<StatusChange>,
+}
+
+/// Posts status change events to webhook URLs.
+///
+/// When a secret is configured, the body is signed with HMAC-SHA256 and the hex encoded
+/// signature is sent in the `X-Weathervane-Signature` header as `sha256=<signature>`.
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    http_client: reqwest::Client,
+    urls: Vec<String>,
+    secret: Option<String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(http_client: &reqwest::Client, urls: Vec<String>, secret: Option<String>) -> Self {
+        Self {
+            http_client: http_client.clone(),
+            urls,
+            secret,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    pub async fn notify(&self, event: &StatusChangeEvent) -> Result<()> {
+        let body = serde_json::to_string(event)?;
+        let signature = self.secret.as_ref().map(|secret| sign(secret, &body));
+
+        let failures = join_all(
+            self.urls
+                .iter()
+                .map(|url| self.deliver(url, &body, signature.as_deref())),
+        )
+        .await
+        .into_iter()
+        .filter(|result| result.is_err())
+        .count();
+
+        if failures > 0 {
+            return Err(anyhow!(
+                "Failed to deliver webhook for {} to {} URLs",
+                event.did,
+                failures
+            ));
+        }
+        Ok(())
+    }
+
+    async fn deliver(&self, url: &str, body: &str, signature: Option<&str>) -> Result<()> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            let mut request = self
+                .http_client
+                .post(url)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .timeout(Duration::from_secs(10))
+                .body(body.to_string());
+            if let Some(signature) = signature {
+                request = request.header(SIGNATURE_HEADER, format!("sha256={}", signature));
+            }
+
+            let result = request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => return Ok(()),
+                Err(err) if attempt < MAX_ATTEMPTS && is_retryable(&err) => {
+                    tracing::warn!(url, attempt, error = ?err, "webhook delivery failed, retrying");
+                }
+                Err(err) => {
+                    tracing::error!(url, attempt, error = ?err, "webhook delivery failed");
+                    return Err(err.into());
+                }
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Network errors, server errors and rate limited deliveries can succeed later. Other client
+/// errors won't.
+fn is_retryable(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }
+        None => !err.is_builder(),
+    }
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::test_server::serve;
+
+    /// Serves a webhook URL that responds with `statuses` in turn, then with `200`, and returns
+    /// the URL along with the number of deliveries.
+    async fn webhook(statuses: &'static [StatusCode]) -> (String, Arc<AtomicU32>) {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        let router = Router::new().route(
+            "/hook",
+            post(move || async move {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst) as usize;
+                statuses.get(attempt).copied().unwrap_or(StatusCode::OK)
+            }),
+        );
+        (format!("{}/hook", serve(router).await), attempts)
+    }
+
+    fn notifier(url: String) -> WebhookNotifier {
+        WebhookNotifier::new(&reqwest::Client::new(), vec![url], None)
+    }
+
+    #[tokio::test]
+    async fn rate_limited_deliveries_are_retried() {
+        let (url, attempts) = webhook(&[StatusCode::TOO_MANY_REQUESTS]).await;
+        notifier(url.clone())
+            .deliver(&url, "{}", None)
+            .await
+            .unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn rejected_deliveries_are_not_retried() {
+        let (url, attempts) = webhook(&[StatusCode::NOT_FOUND]).await;
+        assert!(notifier(url.clone())
+            .deliver(&url, "{}", None)
+            .await
+            .is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,51 +1,111 @@
-use anyhow::Result;
-use moka::future::Cache;
-use tokio::sync::mpsc::Receiver;
-use tokio_util::sync::CancellationToken;
+use anyhow::{anyhow, Result};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::{
+    mpsc::{error::TrySendError, Receiver},
+    Semaphore,
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::Instrument;
 
 use crate::{
-    cache::{new_worker_cache, VerifyResult},
-    identity::IdentityType,
+    cache::VerifyResult, errors::QueueFullError, health::TaskStatus, i18n::Reason,
+    identity::Identity, verifier::Verifier,
 };
 
 pub struct QueueWork {
     pub(crate) did: String,
     pub(crate) tx: tokio::sync::mpsc::Sender<VerifyWork>,
-    pub(crate) identities: Vec<IdentityType>,
+    pub(crate) identities: Vec<Identity>,
+    /// Verify identities even if a cached result exists.
+    pub(crate) refresh: bool,
+    /// The span of the request that queued the work, used as the parent of its processing span.
+    pub(crate) span: tracing::Span,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum VerifyWork {
-    Ok(IdentityType),
-    Error(IdentityType, String),
+    Ok(Identity),
+    Error(Identity, Reason),
+    /// The identity could not be checked, such as when a request timed out.
+    Unreachable(Identity, Reason),
     Done(),
 }
 
+impl VerifyWork {
+    /// Returns the identity and, if it was not verified, the reason.
+    pub(crate) fn into_outcome(self) -> Option<(Identity, Option<Reason>)> {
+        match self {
+            VerifyWork::Ok(identity) => Some((identity, None)),
+            VerifyWork::Error(identity, reason) | VerifyWork::Unreachable(identity, reason) => {
+                Some((identity, Some(reason)))
+            }
+            VerifyWork::Done() => None,
+        }
+    }
+}
+
+/// Counts the work taken off the queue, used to tell a visitor how far along the queue their
+/// request is. Clones share the same count.
+#[derive(Clone, Default)]
+pub struct QueueProgress(Arc<AtomicU64>);
+
+impl QueueProgress {
+    pub(crate) fn taken(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn take(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 pub struct VerifyTask {
-    pub(crate) http_client: reqwest::Client,
-    pub(crate) plc_hostname: String,
+    pub(crate) verifier: Verifier,
     pub(crate) cancellation_token: CancellationToken,
 
-    cache: Cache<String, VerifyResult>,
+    concurrency: Arc<Semaphore>,
+    tracker: TaskTracker,
+    status: TaskStatus,
+    progress: QueueProgress,
 }
 
 impl VerifyTask {
+    /// Creates a task that processes at most `concurrency` pieces of queued work at a time.
+    ///
+    /// With a host rate limit in the verifier options, verifications over the limit of the host
+    /// they contact are reported as unreachable so that identities listed by visitors can't be
+    /// used to flood a website, and workers aren't held waiting for a busy host.
     pub fn new(
-        http_client: &reqwest::Client,
-        plc_hostname: String,
+        verifier: Verifier,
+        concurrency: usize,
         cancellation_token: CancellationToken,
     ) -> Self {
-        let cache = new_worker_cache();
         Self {
-            http_client: http_client.clone(),
+            verifier,
             cancellation_token,
-            plc_hostname,
-            cache,
+            concurrency: Arc::new(Semaphore::new(concurrency.max(1))),
+            tracker: TaskTracker::new(),
+            status: TaskStatus::default(),
+            progress: QueueProgress::default(),
         }
     }
 
+    /// Whether the task is processing queued work.
+    pub fn status(&self) -> TaskStatus {
+        self.status.clone()
+    }
+
+    /// How much work has been taken off the queue.
+    pub fn progress(&self) -> QueueProgress {
+        self.progress.clone()
+    }
+
     pub async fn run_background(&self, rx: &mut Receiver<QueueWork>) -> Result<()> {
         tracing::debug!("VerifyTask started");
+        let _running = self.status.start();
 
         loop {
             tokio::select! {
@@ -55,9 +115,36 @@ impl VerifyTask {
                 r = rx.recv() => {
                     match r {
                         Some(work) => {
-                            if let Err(err) = self.process_work(&work).await {
-                                tracing::error!("DomainImporterTask task failed: {}", err);
+                            self.progress.take();
+                            let permit = tokio::select! {
+                                () = self.cancellation_token.cancelled() => {
+                                    break;
+                                },
+                                permit = self.concurrency.clone().acquire_owned() => permit?,
+                            };
+                            // Nobody is waiting for the results, such as a visitor that closed
+                            // the DID page while the work was queued.
+                            if work.tx.is_closed() {
+                                tracing::debug!(did = work.did, "skipping abandoned work");
+                                continue;
                             }
+                            let verifier = self.verifier.clone();
+                            let span = tracing::info_span!(
+                                parent: &work.span,
+                                "process_work",
+                                did = work.did,
+                                identities = work.identities.len(),
+                                refresh = work.refresh,
+                            );
+                            self.tracker.spawn(
+                                async move {
+                                    if let Err(err) = process_work(&verifier, &work).await {
+                                        tracing::error!("VerifyTask task failed: {}", err);
+                                    }
+                                    drop(permit);
+                                }
+                                .instrument(span),
+                            );
                         }
                         None => {
                             break;
@@ -67,100 +154,178 @@ impl VerifyTask {
             }
         }
 
-        tracing::debug!("VerifyTask started");
+        self.tracker.close();
+        self.tracker.wait().await;
+
+        tracing::debug!("VerifyTask stopped");
 
         Ok(())
     }
+}
 
-    async fn process_work(&self, work: &QueueWork) -> Result<()> {
-        let mut identity_queue = work.identities.clone();
-        while let Some(identity) = identity_queue.pop() {
-            let cache_key = format!("{}-{}", work.did, identity.to_key());
+/// Queues verification of the identities and returns the channel their results are sent to,
+/// ending with [`VerifyWork::Done`]. The work is rejected with a [`QueueFullError`] when the queue
+/// is full.
+pub(crate) fn queue_work(
+    verify_work_tx: &tokio::sync::mpsc::Sender<QueueWork>,
+    did: &str,
+    identities: Vec<Identity>,
+    refresh: bool,
+) -> Result<Receiver<VerifyWork>> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<VerifyWork>(identities.len() + 1);
+    let queued = verify_work_tx.try_send(QueueWork {
+        did: did.to_string(),
+        tx,
+        identities,
+        refresh,
+        span: tracing::Span::current(),
+    });
+    match queued {
+        Ok(()) => Ok(rx),
+        Err(TrySendError::Full(_)) => {
+            tracing::warn!("verification queue is full");
+            Err(QueueFullError.into())
+        }
+        Err(TrySendError::Closed(_)) => Err(anyhow!("verification queue is closed")),
+    }
+}
 
-            if let Some(verify_result) = self.cache.get(&cache_key).await {
-                let message = match verify_result {
-                    VerifyResult::Found => VerifyWork::Ok(identity),
-                    VerifyResult::NotFound => {
-                        VerifyWork::Error(identity, "DID not found".to_string())
-                    }
-                };
-                if let Err(err) = work.tx.send(message).await {
-                    tracing::error!(error = ?err, "Failed to write to work channel.");
-                }
-                continue;
-            }
+/// Queues verification of the identities and waits for the result of each one. The work is
+/// rejected with a [`QueueFullError`] when the queue is full.
+pub(crate) async fn verify_queued(
+    verify_work_tx: &tokio::sync::mpsc::Sender<QueueWork>,
+    did: &str,
+    identities: Vec<Identity>,
+    refresh: bool,
+) -> Result<Vec<VerifyWork>> {
+    let mut rx = queue_work(verify_work_tx, did, identities, refresh)?;
+
+    let mut results = Vec::new();
+    while let Some(work) = rx.recv().await {
+        if work == VerifyWork::Done() {
+            break;
+        }
+        results.push(work);
+    }
+    Ok(results)
+}
 
-            let verify_result = match identity.clone() {
-                IdentityType::Handle(identity_value) => {
-                    handle::validate(&self.http_client, &work.did, &identity_value).await
-                }
-                IdentityType::GitHub(identity_value) => {
-                    github::validate(&self.http_client, &work.did, &identity_value).await
-                }
-                IdentityType::Domain(identity_value) => {
-                    domain::validate(&work.did, &identity_value).await
-                }
-                IdentityType::Website(identity_value) => {
-                    website::validate(&self.http_client, &work.did, &identity_value).await
-                }
-                IdentityType::DIDMethodPLC(identity_value) => {
-                    did_method_plc::validate(
-                        &self.http_client,
-                        &self.plc_hostname,
-                        &work.did,
-                        &identity_value,
-                    )
-                    .await
-                }
-                IdentityType::DIDMethodWeb(identity_value) => {
-                    did_method_web::validate(&self.http_client, &work.did, &identity_value).await
-                }
-                _ => VerifyResult::NotFound,
-            };
-
-            self.cache.insert(cache_key, verify_result.clone()).await;
-            let message = match verify_result {
-                VerifyResult::Found => VerifyWork::Ok(identity),
-                VerifyResult::NotFound => VerifyWork::Error(identity, "DID not found".to_string()),
-            };
-            if let Err(err) = work.tx.send(message).await {
-                tracing::error!(error = ?err, "Failed to write to work channel.");
+/// Verifies each identity of the work, stopping early when nobody is waiting for the results.
+///
+/// Dropping a verification is safe when another request is waiting for the same identity: the
+/// cache hands the verification over to that request.
+async fn process_work(verifier: &Verifier, work: &QueueWork) -> Result<()> {
+    let mut identity_queue = work.identities.clone();
+    while let Some(identity) = identity_queue.pop() {
+        let verify = async {
+            if work.refresh {
+                verifier.refresh_identity(&work.did, &identity).await
+            } else {
+                verifier.verify_identity(&work.did, &identity).await
             }
-        }
-        if let Err(err) = work.tx.send(VerifyWork::Done()).await {
+        };
+        let verify_result = tokio::select! {
+            () = work.tx.closed() => {
+                tracing::debug!(
+                    did = work.did,
+                    remaining = identity_queue.len() + 1,
+                    "receiver closed, abandoning work"
+                );
+                return Ok(());
+            },
+            verify_result = verify => verify_result,
+        };
+        let message = match verify_result {
+            VerifyResult::Found => VerifyWork::Ok(identity),
+            VerifyResult::NotFound(reason) => VerifyWork::Error(identity, reason),
+            VerifyResult::Unreachable(reason) => VerifyWork::Unreachable(identity, reason),
+        };
+        if let Err(err) = work.tx.send(message).await {
             tracing::error!(error = ?err, "Failed to write to work channel.");
         }
-
-        Ok(())
     }
+    if let Err(err) = work.tx.send(VerifyWork::Done()).await {
+        tracing::error!(error = ?err, "Failed to write to work channel.");
+    }
+
+    Ok(())
 }
 
 pub(crate) mod domain {
-    use crate::resolve::resolve_handle_dns;
+    use async_trait::async_trait;
+
+    use crate::{did::is_valid_hostname, identity::IdentityValidator, resolve::resolve_handle_dns};
 
-    use super::VerifyResult;
+    use super::{Reason, VerifyResult};
 
+    #[tracing::instrument(name = "validate_domain")]
     pub(crate) async fn validate(did: &str, identity_value: &str) -> VerifyResult {
         tracing::info!(handle = identity_value, did = did, "processing domain");
         let trimmed = identity_value
             .strip_prefix("dns:")
             .unwrap_or(identity_value);
-        if let Ok(found_did) = resolve_handle_dns(trimmed).await {
-            tracing::info!(did = found_did, "did resolved");
-            if found_did == did {
-                return VerifyResult::Found;
+        match resolve_handle_dns(trimmed).await {
+            Ok(found_did) if found_did == did => {
+                tracing::info!(did = found_did, "did resolved");
+                VerifyResult::Found
             }
+            Ok(found_did) => {
+                tracing::info!(did = found_did, "did resolved");
+                VerifyResult::NotFound(
+                    Reason::new("reason-txt-mismatch")
+                        .arg("domain", trimmed)
+                        .arg("found", found_did)
+                        .arg("expected", did),
+                )
+            }
+            Err(err) => VerifyResult::from_error(
+                Reason::new("reason-txt-lookup-failed").arg("domain", trimmed),
+                &err,
+            ),
+        }
+    }
+
+    pub(crate) struct DomainValidator;
+
+    #[async_trait]
+    impl IdentityValidator for DomainValidator {
+        fn kind(&self) -> &str {
+            "domain"
+        }
+
+        fn label(&self) -> &str {
+            "DNS"
         }
 
-        VerifyResult::NotFound
+        fn parse(&self, value: &str) -> Option<String> {
+            value
+                .strip_prefix("dns:")
+                .is_some_and(is_valid_hostname)
+                .then(|| value.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(did, identity_value).await
+        }
+
+        fn target_host(&self, identity_value: &str) -> Option<String> {
+            identity_value.strip_prefix("dns:").map(str::to_string)
+        }
+
+        fn guide(&self) -> &str {
+            "Add \"dns:\" + domain to alsoKnownAs and create a TXT record on the domain with the value \"did=\" + DID."
+        }
     }
 }
 
 pub(crate) mod did_method_plc {
-    use crate::did_plc::plc_query;
+    use async_trait::async_trait;
 
-    use super::VerifyResult;
+    use crate::{did_plc::plc_query, identity::IdentityValidator};
 
+    use super::{Reason, VerifyResult};
+
+    #[tracing::instrument(name = "validate_did_method_plc", skip(http_client))]
     pub(crate) async fn validate(
         http_client: &reqwest::Client,
         plc_hostname: &str,
@@ -174,24 +339,77 @@ pub(crate) mod did_method_plc {
         );
 
         let plc_results = plc_query(http_client, plc_hostname, identity_value).await;
-        if plc_results.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let (_, values) = plc_results.unwrap();
+        let document = match plc_results {
+            Ok(document) => document,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-resolve-failed").arg("value", identity_value),
+                    &err,
+                )
+            }
+        };
 
-        if values.iter().any(|x| x == did) {
+        if document.also_known_as.iter().any(|x| x == did) {
             return VerifyResult::Found;
         }
 
-        VerifyResult::NotFound
+        VerifyResult::NotFound(
+            Reason::new("reason-also-known-as-missing")
+                .arg("value", identity_value)
+                .arg("did", did)
+                .arg("found", document.also_known_as.join(", ")),
+        )
+    }
+
+    pub(crate) struct DidMethodPlcValidator {
+        http_client: reqwest::Client,
+        plc_hostname: String,
+    }
+
+    impl DidMethodPlcValidator {
+        pub(crate) fn new(http_client: &reqwest::Client, plc_hostname: &str) -> Self {
+            Self {
+                http_client: http_client.clone(),
+                plc_hostname: plc_hostname.to_string(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IdentityValidator for DidMethodPlcValidator {
+        fn kind(&self) -> &str {
+            "did_method_plc"
+        }
+
+        fn label(&self) -> &str {
+            "DID-PLC"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            value.starts_with("did:plc:").then(|| value.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(&self.http_client, &self.plc_hostname, did, identity_value).await
+        }
+
+        fn guide(&self) -> &str {
+            "Add the did:plc DID to alsoKnownAs and add this DID to the alsoKnownAs of the did:plc document."
+        }
     }
 }
 
 pub(crate) mod did_method_web {
-    use crate::did_web::web_query;
+    use async_trait::async_trait;
+
+    use crate::{
+        did_web::{did_web_host, web_query},
+        identity::IdentityValidator,
+    };
 
-    use super::VerifyResult;
+    use super::{Reason, VerifyResult};
 
+    #[tracing::instrument(name = "validate_did_method_web", skip(http_client))]
     pub(crate) async fn validate(
         http_client: &reqwest::Client,
         did: &str,
@@ -204,46 +422,152 @@ pub(crate) mod did_method_web {
         );
 
         let web_results = web_query(http_client, identity_value).await;
-        if web_results.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let (_, values) = web_results.unwrap();
+        let document = match web_results {
+            Ok(document) => document,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-resolve-failed").arg("value", identity_value),
+                    &err,
+                )
+            }
+        };
 
-        if values.iter().any(|x| x == did) {
+        if document.also_known_as.iter().any(|x| x == did) {
             return VerifyResult::Found;
         }
 
-        VerifyResult::NotFound
+        VerifyResult::NotFound(
+            Reason::new("reason-also-known-as-missing")
+                .arg("value", identity_value)
+                .arg("did", did)
+                .arg("found", document.also_known_as.join(", ")),
+        )
+    }
+
+    pub(crate) struct DidMethodWebValidator {
+        http_client: reqwest::Client,
+    }
+
+    impl DidMethodWebValidator {
+        pub(crate) fn new(http_client: &reqwest::Client) -> Self {
+            Self {
+                http_client: http_client.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IdentityValidator for DidMethodWebValidator {
+        fn kind(&self) -> &str {
+            "did_method_web"
+        }
+
+        fn label(&self) -> &str {
+            "DID-WEB"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            value.starts_with("did:web:").then(|| value.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(&self.http_client, did, identity_value).await
+        }
+
+        fn target_host(&self, identity_value: &str) -> Option<String> {
+            did_web_host(identity_value)
+        }
+
+        fn guide(&self) -> &str {
+            "Add the did:web DID to alsoKnownAs and add this DID to the alsoKnownAs of the did:web document."
+        }
     }
 }
 
 pub(crate) mod handle {
-    use crate::resolve::resolve_handle;
+    use async_trait::async_trait;
 
-    use super::VerifyResult;
+    use crate::{identity::IdentityValidator, resolve::resolve_handle};
 
+    use super::{Reason, VerifyResult};
+
+    #[tracing::instrument(name = "validate_handle", skip(http_client))]
     pub(crate) async fn validate(
         http_client: &reqwest::Client,
         did: &str,
         identity_value: &str,
     ) -> VerifyResult {
         tracing::info!(handle = identity_value, did = did, "processing handle");
-        if let Ok(found_did) = resolve_handle(http_client, identity_value).await {
-            tracing::info!(did = found_did, "did resolved");
-            if found_did == did {
-                return VerifyResult::Found;
+        match resolve_handle(http_client, identity_value).await {
+            Ok(found_did) if found_did == did => {
+                tracing::info!(did = found_did, "did resolved");
+                VerifyResult::Found
+            }
+            Ok(found_did) => {
+                tracing::info!(did = found_did, "did resolved");
+                VerifyResult::NotFound(
+                    Reason::new("reason-handle-mismatch")
+                        .arg("handle", identity_value)
+                        .arg("found", found_did)
+                        .arg("expected", did),
+                )
             }
+            Err(err) => VerifyResult::from_error(
+                Reason::new("reason-resolve-failed").arg("value", identity_value),
+                &err,
+            ),
         }
+    }
 
-        VerifyResult::NotFound
+    pub(crate) struct HandleValidator {
+        http_client: reqwest::Client,
+    }
+
+    impl HandleValidator {
+        pub(crate) fn new(http_client: &reqwest::Client) -> Self {
+            Self {
+                http_client: http_client.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IdentityValidator for HandleValidator {
+        fn kind(&self) -> &str {
+            "handle"
+        }
+
+        fn label(&self) -> &str {
+            "Handle"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            value.starts_with("at://").then(|| value.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(&self.http_client, did, identity_value).await
+        }
+
+        fn target_host(&self, identity_value: &str) -> Option<String> {
+            identity_value.strip_prefix("at://").map(str::to_string)
+        }
+
+        fn guide(&self) -> &str {
+            "Add \"at://\" + handle to alsoKnownAs and create a TXT record at \"_atproto.\" + handle with the value \"did=\" + DID."
+        }
     }
 }
 
 pub(crate) mod github {
+    use async_trait::async_trait;
     use serde::Deserialize;
 
-    use super::{handle, VerifyResult};
+    use crate::identity::IdentityValidator;
 
+    use super::{handle, Reason, VerifyResult};
+
+    #[tracing::instrument(name = "validate_github", skip(http_client))]
     pub(crate) async fn validate(
         http_client: &reqwest::Client,
         did: &str,
@@ -255,17 +579,28 @@ pub(crate) mod github {
                 identity_value
             ))
             .send()
-            .await;
-        if response.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let response = response.unwrap();
+            .await
+            .and_then(|response| response.error_for_status());
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-github-fetch-failed").arg("user", identity_value),
+                    &err.into(),
+                )
+            }
+        };
 
         let social_accounts: Result<Vec<GitHubSocial>, _> = response.json().await;
-        if social_accounts.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let social_accounts = social_accounts.unwrap();
+        let social_accounts = match social_accounts {
+            Ok(social_accounts) => social_accounts,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-github-read-failed").arg("user", identity_value),
+                    &err.into(),
+                )
+            }
+        };
 
         let bsky_handle = social_accounts
             .iter()
@@ -283,7 +618,9 @@ pub(crate) mod github {
                     })
             });
         if bsky_handle.is_none() {
-            return VerifyResult::NotFound;
+            return VerifyResult::NotFound(
+                Reason::new("reason-github-no-bluesky").arg("user", identity_value),
+            );
         }
         let bsky_handle = bsky_handle.unwrap();
 
@@ -295,57 +632,108 @@ pub(crate) mod github {
         provider: String,
         url: String,
     }
+
+    pub(crate) struct GitHubValidator {
+        http_client: reqwest::Client,
+    }
+
+    impl GitHubValidator {
+        pub(crate) fn new(http_client: &reqwest::Client) -> Self {
+            Self {
+                http_client: http_client.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IdentityValidator for GitHubValidator {
+        fn kind(&self) -> &str {
+            "github"
+        }
+
+        fn label(&self) -> &str {
+            "GitHub"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            value
+                .strip_prefix("https://github.com/")
+                .map(|trimmed| {
+                    if let Some((first, _)) = trimmed.split_once("/") {
+                        first
+                    } else {
+                        trimmed
+                    }
+                })
+                .filter(|username| !username.is_empty())
+                .map(|username| username.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(&self.http_client, did, identity_value).await
+        }
+
+        fn target_host(&self, _identity_value: &str) -> Option<String> {
+            Some("github.com".to_string())
+        }
+
+        fn guide(&self) -> &str {
+            "Add your GitHub profile URL to alsoKnownAs and add a Bluesky social account to your GitHub profile that links to a handle for this DID."
+        }
+    }
 }
 
 pub(crate) mod website {
+    use async_trait::async_trait;
     use scraper::{Html, Selector};
     use std::str::FromStr;
     use std::time::Duration;
     use url::Url;
 
-    use super::VerifyResult;
+    use crate::identity::IdentityValidator;
+
+    use super::{Reason, VerifyResult};
 
+    #[tracing::instrument(name = "validate_website", skip(http_client))]
     pub(crate) async fn validate(
         http_client: &reqwest::Client,
         did: &str,
         identity_value: &str,
     ) -> VerifyResult {
-        let url = Url::from_str(identity_value);
-        if url.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let url = url.unwrap();
-        if url.scheme() != "http" && url.scheme() != "https" {
-            return VerifyResult::NotFound;
-        }
-        if url.host().is_none() {
-            return VerifyResult::NotFound;
-        }
-        if !url.username().is_empty() || url.password().is_some() {
-            return VerifyResult::NotFound;
-        }
-        if url.path().is_empty() {
-            return VerifyResult::NotFound;
-        }
-        if url.query().is_some() {
-            return VerifyResult::NotFound;
-        }
+        let url = match Url::from_str(identity_value) {
+            Ok(url) if is_supported(&url) => url,
+            _ => {
+                return VerifyResult::NotFound(
+                    Reason::new("reason-website-unsupported").arg("url", identity_value),
+                )
+            }
+        };
 
         let response = http_client
             .get(url.to_string())
             .timeout(Duration::from_secs(3))
             .send()
-            .await;
-        if response.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let response = response.unwrap();
-
-        let body = response.text().await;
-        if body.is_err() {
-            return VerifyResult::NotFound;
-        }
-        let body = body.unwrap();
+            .await
+            .and_then(|response| response.error_for_status());
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-website-fetch-failed").arg("url", url.as_str()),
+                    &err.into(),
+                )
+            }
+        };
+
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(err) => {
+                return VerifyResult::from_error(
+                    Reason::new("reason-website-read-failed").arg("url", url.as_str()),
+                    &err.into(),
+                )
+            }
+        };
 
         let document = Html::parse_document(&body);
 
@@ -353,16 +741,252 @@ pub(crate) mod website {
             Selector::parse(r#"link[rel~="did"]"#).unwrap(),
             Selector::parse(r#"link[rel~="me"]"#).unwrap(),
         ];
+        let mut found = Vec::new();
         for selector in selectors {
             for element in document.select(&selector) {
                 if let Some(found_did) = element.value().attr("href") {
                     if found_did == did {
                         return VerifyResult::Found;
                     }
+                    found.push(found_did);
                 }
             }
         }
 
-        VerifyResult::NotFound
+        VerifyResult::NotFound(
+            Reason::new("reason-website-no-link")
+                .arg("url", url.as_str())
+                .arg("did", did)
+                .arg("found", found.join(", ")),
+        )
+    }
+
+    /// Only http and https URLs with a host and path, and without credentials or a query, are
+    /// fetched.
+    fn is_supported(url: &Url) -> bool {
+        (url.scheme() == "http" || url.scheme() == "https")
+            && url.host().is_some()
+            && url.username().is_empty()
+            && url.password().is_none()
+            && !url.path().is_empty()
+            && url.query().is_none()
+    }
+
+    pub(crate) struct WebsiteValidator {
+        http_client: reqwest::Client,
+    }
+
+    impl WebsiteValidator {
+        pub(crate) fn new(http_client: &reqwest::Client) -> Self {
+            Self {
+                http_client: http_client.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IdentityValidator for WebsiteValidator {
+        fn kind(&self) -> &str {
+            "website"
+        }
+
+        fn label(&self) -> &str {
+            "WWW"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            (value.starts_with("https://") || value.starts_with("http://"))
+                .then(|| value.to_string())
+        }
+
+        async fn verify(&self, did: &str, identity_value: &str) -> VerifyResult {
+            validate(&self.http_client, did, identity_value).await
+        }
+
+        fn target_host(&self, identity_value: &str) -> Option<String> {
+            url::Url::parse(identity_value)
+                .ok()?
+                .host_str()
+                .map(str::to_string)
+        }
+
+        fn guide(&self) -> &str {
+            "Add the website URL to alsoKnownAs and include a <link rel=\"did\" href=\"DID\"> element in the page."
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use axum::{response::Html, routing::get, Router};
+
+        use super::*;
+        use crate::test_server::serve_failures;
+
+        const DID: &str = "did:plc:cbkjy5n7bk3ax2wplmtjofq2";
+
+        async fn verify(path: &str) -> VerifyResult {
+            let base = serve_failures(Router::new().route(
+                "/linked",
+                get(|| async { Html(format!(r#"<link rel="did" href="{}">"#, DID)) }),
+            ))
+            .await;
+            validate(&reqwest::Client::new(), DID, &format!("{}{}", base, path)).await
+        }
+
+        #[tokio::test]
+        async fn linked_pages_are_found() {
+            assert_eq!(verify("/linked").await, VerifyResult::Found);
+        }
+
+        #[tokio::test]
+        async fn server_errors_are_unreachable() {
+            assert!(matches!(
+                verify("/busy").await,
+                VerifyResult::Unreachable(_)
+            ));
+        }
+
+        #[tokio::test]
+        async fn timeouts_are_unreachable() {
+            assert!(matches!(
+                verify("/slow").await,
+                VerifyResult::Unreachable(_)
+            ));
+        }
+
+        #[tokio::test]
+        async fn missing_pages_are_not_found() {
+            assert!(matches!(
+                verify("/missing").await,
+                VerifyResult::NotFound(_)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{identity::IdentityValidator, rate_limit::RateLimit, verifier::VerifierOptions};
+
+    /// Verifies every identity, contacting the host before the slash in its value.
+    struct HostValidator;
+
+    #[async_trait]
+    impl IdentityValidator for HostValidator {
+        fn kind(&self) -> &str {
+            "test"
+        }
+
+        fn label(&self) -> &str {
+            "Test"
+        }
+
+        fn parse(&self, value: &str) -> Option<String> {
+            value.strip_prefix("test:").map(str::to_string)
+        }
+
+        async fn verify(&self, _did: &str, _identity_value: &str) -> VerifyResult {
+            VerifyResult::Found
+        }
+
+        fn guide(&self) -> &str {
+            ""
+        }
+
+        fn target_host(&self, identity_value: &str) -> Option<String> {
+            identity_value.split('/').next().map(str::to_string)
+        }
+    }
+
+    fn test_identity(value: &str) -> Identity {
+        Identity {
+            kind: "test".to_string(),
+            label: "Test".to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    async fn queue(
+        verify_work_tx: &tokio::sync::mpsc::Sender<QueueWork>,
+        values: &[&str],
+    ) -> Vec<VerifyWork> {
+        verify_queued(
+            verify_work_tx,
+            "did:plc:cbkjy5n7bk3ax2wplmtjofq2",
+            values.iter().map(|value| test_identity(value)).collect(),
+            false,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn throttled_hosts_do_not_block_other_hosts() {
+        let verifier = Verifier::new(
+            &reqwest::Client::new(),
+            VerifierOptions {
+                host_rate_limit: Some(RateLimit {
+                    burst: 1,
+                    period: Duration::from_secs(3600),
+                }),
+                ..VerifierOptions::default()
+            },
+        )
+        .with_validator(Arc::new(HostValidator));
+        let cancellation_token = CancellationToken::new();
+        let verify_task = VerifyTask::new(verifier, 1, cancellation_token.clone());
+        let (verify_work_tx, mut verify_work_rx) = tokio::sync::mpsc::channel::<QueueWork>(4);
+        let runner =
+            tokio::spawn(async move { verify_task.run_background(&mut verify_work_rx).await });
+
+        let (throttled, other) = tokio::time::timeout(Duration::from_secs(5), async {
+            let throttled = queue(&verify_work_tx, &["a.example/1", "a.example/2"]).await;
+            let other = queue(&verify_work_tx, &["b.example/1"]).await;
+            (throttled, other)
+        })
+        .await
+        .expect("a throttled host blocked the queue");
+
+        // Only one verification of the throttled host fits in its limit.
+        assert_eq!(throttled.len(), 2);
+        assert!(throttled
+            .iter()
+            .any(|work| matches!(work, VerifyWork::Ok(_))));
+        assert!(throttled
+            .iter()
+            .any(|work| matches!(work, VerifyWork::Unreachable(_, _))));
+        assert_eq!(other, vec![VerifyWork::Ok(test_identity("b.example/1"))]);
+
+        cancellation_token.cancel();
+        runner.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_queues_reject_work() {
+        let (verify_work_tx, _verify_work_rx) = tokio::sync::mpsc::channel::<QueueWork>(1);
+        let (tx, _rx) = tokio::sync::mpsc::channel::<VerifyWork>(1);
+        verify_work_tx
+            .try_send(QueueWork {
+                did: "did:plc:cbkjy5n7bk3ax2wplmtjofq2".to_string(),
+                tx,
+                identities: Vec::new(),
+                refresh: false,
+                span: tracing::Span::none(),
+            })
+            .unwrap();
+
+        let err = verify_queued(
+            &verify_work_tx,
+            "did:plc:cbkjy5n7bk3ax2wplmtjofq2",
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.is::<QueueFullError>());
     }
 }